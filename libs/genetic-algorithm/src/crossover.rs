@@ -0,0 +1,323 @@
+use crate::*;
+use rand::seq::index;
+
+pub trait CrossoverMethod {
+    fn crossover(
+        &self,
+        rng: &mut dyn RngCore,
+        parent_a: &Chromosome,
+        parent_b: &Chromosome,
+    ) -> Chromosome;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct UniformCrossover;
+
+impl UniformCrossover {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl CrossoverMethod for UniformCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        let parent_a = parent_a.iter();
+        let parent_b = parent_b.iter();
+    
+        parent_a
+            .zip(parent_b)
+            .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
+            .collect()
+    }
+}
+
+/// Takes genes from the first parent up to a random point, and from the
+/// second one after it.
+#[derive(Clone, Debug, Default)]
+pub struct SinglePointCrossover;
+
+impl SinglePointCrossover {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl CrossoverMethod for SinglePointCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        KPointCrossover::new(1).crossover(rng, parent_a, parent_b)
+    }
+}
+
+/// Cuts parents at `points` random points, taking genes from either parent
+/// in turns.
+#[derive(Clone, Debug)]
+pub struct KPointCrossover {
+    points: usize,
+}
+
+impl KPointCrossover {
+    pub fn new(points: usize) -> Self {
+        assert!(points > 0);
+
+        Self { points }
+    }
+}
+
+impl CrossoverMethod for KPointCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        // cutting before the first gene would just swap the parents; short
+        // chromosomes get cut wherever they can be
+        let cuts = parent_a.len().saturating_sub(1);
+        let mut points = index::sample(rng, cuts, self.points.min(cuts)).into_vec();
+        points.sort_unstable();
+
+        let mut points = points.into_iter().map(|point| point + 1).peekable();
+        let mut from_a = true;
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .enumerate()
+            .map(|(idx, (&a, &b))| {
+                if points.next_if_eq(&idx).is_some() {
+                    from_a = !from_a;
+                }
+                if from_a { a } else { b }
+            })
+            .collect()
+    }
+}
+
+/// BLX-α: every gene is drawn uniformly from the range between the parents'
+/// genes, widened by `alpha` times its width on both sides.
+#[derive(Clone, Debug)]
+pub struct BlendCrossover {
+    alpha: f32,
+}
+
+impl BlendCrossover {
+    /// `alpha` of 0.0 keeps children between their parents, while 0.5 (the
+    /// usual choice) lets them explore about as far around.
+    pub fn new(alpha: f32) -> Self {
+        assert!(alpha >= 0.0);
+
+        Self { alpha }
+    }
+}
+
+impl CrossoverMethod for BlendCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(&a, &b)| {
+                let spread = self.alpha * (a - b).abs();
+                rng.gen_range(a.min(b) - spread..=a.max(b) + spread)
+            })
+            .collect()
+    }
+}
+
+/// Simulated binary crossover (SBX), which spreads children around their
+/// parents the way single-point crossover does with binary genes: on
+/// average right between them, mostly close to either of them.
+#[derive(Clone, Debug)]
+pub struct SimulatedBinaryCrossover {
+    /// Distribution index; the higher, the closer children stay to their
+    /// parents (usually somewhere within `2.0..=20.0`)
+    eta: f32,
+}
+
+impl SimulatedBinaryCrossover {
+    pub fn new(eta: f32) -> Self {
+        assert!(eta >= 0.0);
+
+        Self { eta }
+    }
+}
+
+impl CrossoverMethod for SimulatedBinaryCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+
+        let power = 1.0 / (self.eta + 1.0);
+
+        parent_a
+            .iter()
+            .zip(parent_b.iter())
+            .map(|(&a, &b)| {
+                let u: f32 = rng.gen();
+                let beta = if u <= 0.5 {
+                    (2.0 * u).powf(power)
+                } else {
+                    (1.0 / (2.0 * (1.0 - u))).powf(power)
+                };
+
+                // SBX makes two children, mirrored around the parents'
+                // midpoint; this keeps either of them
+                let (mean, half) = ((a + b) / 2.0, beta * (a - b) / 2.0);
+                if rng.gen_bool(0.5) { mean + half } else { mean - half }
+            })
+            .collect()
+    }
+}
+
+/// Uniform crossover of whole blocks of genes, e.g. every neuron's bias and
+/// weights (see `lib_neural_network::Network::neuron_genes()`), so that
+/// children inherit working neurons instead of a mix of their genes.
+#[derive(Clone, Debug)]
+pub struct LayerAwareCrossover {
+    /// Lengths of consecutive blocks, adding up to the chromosomes' length
+    blocks: Vec<usize>,
+}
+
+impl LayerAwareCrossover {
+    /// Panics if there are no blocks, or if any of them is empty.
+    pub fn new(blocks: impl IntoIterator<Item = usize>) -> Self {
+        let blocks: Vec<usize> = blocks.into_iter().collect();
+        assert!(!blocks.is_empty() && !blocks.contains(&0), "got empty gene blocks: {blocks:?}");
+
+        Self { blocks }
+    }
+}
+
+impl CrossoverMethod for LayerAwareCrossover {
+    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
+        assert_eq!(parent_a.len(), parent_b.len());
+        assert_eq!(self.blocks.iter().sum::<usize>(), parent_a.len(), "blocks don't cover the chromosome");
+
+        let mut genes = Vec::with_capacity(parent_a.len());
+        let mut start = 0;
+
+        for &block in &self.blocks {
+            let parent = if rng.gen_bool(0.5) { parent_a } else { parent_b };
+            genes.extend(parent.iter().skip(start).take(block));
+            start += block;
+        }
+
+        Chromosome::new(genes)
+    }
+}
+
+#[cfg(test)]
+fn crossover_parents(len: usize) -> (Chromosome, Chromosome) {
+    let parent_a = (0..len).map(|gene| gene as f32).collect();
+    let parent_b = (0..len).map(|gene| -(gene as f32) - 1.0).collect();
+    (parent_a, parent_b)
+}
+
+/// For every gene of `child`, whether it comes from the first parent.
+#[cfg(test)]
+fn from_parent_a(child: &Chromosome) -> Vec<bool> {
+    child.iter().map(|&gene| gene >= 0.0).collect()
+}
+
+#[test]
+fn test_uniform() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let (parent_a, parent_b) = crossover_parents(1000);
+
+    let child = UniformCrossover.crossover(&mut rng, &parent_a, &parent_b);
+    let from_a = from_parent_a(&child);
+    assert_eq!(child.len(), 1000);
+    assert!(child.iter().enumerate().all(|(idx, &gene)| gene == parent_a[idx] || gene == parent_b[idx]));
+    assert_eq!(from_a.iter().filter(|&&from_a| from_a).count(), 485);
+}
+
+#[test]
+fn test_point_crossovers() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let (parent_a, parent_b) = crossover_parents(20);
+
+    for points in 1..=4 {
+        let method = KPointCrossover::new(points);
+
+        for _ in 0..100 {
+            let child = method.crossover(&mut rng, &parent_a, &parent_b);
+            let from_a = from_parent_a(&child);
+
+            assert_eq!(child.len(), 20);
+            assert!(child.iter().enumerate().all(|(idx, &gene)| gene == parent_a[idx] || gene == parent_b[idx]));
+            assert!(from_a[0]);
+            assert_eq!(from_a.windows(2).filter(|pair| pair[0] != pair[1]).count(), points);
+        }
+    }
+
+    let child = SinglePointCrossover.crossover(&mut rng, &parent_a, &parent_b);
+    let from_a = from_parent_a(&child);
+    assert_eq!(from_a.windows(2).filter(|pair| pair[0] != pair[1]).count(), 1);
+
+    // more points than places to cut at
+    let (parent_a, parent_b) = crossover_parents(3);
+    let child = KPointCrossover::new(10).crossover(&mut rng, &parent_a, &parent_b);
+    assert_eq!(child, Chromosome::new(vec![0.0, -2.0, 2.0]));
+
+    let (parent_a, parent_b) = crossover_parents(0);
+    assert!(KPointCrossover::new(2).crossover(&mut rng, &parent_a, &parent_b).is_empty());
+}
+
+#[test]
+fn test_blend() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let parent_a = Chromosome::new(vec![1.0; 1000]);
+    let parent_b = Chromosome::new(vec![3.0; 1000]);
+
+    let child = BlendCrossover::new(0.5).crossover(&mut rng, &parent_a, &parent_b);
+    assert_eq!(child.len(), 1000);
+    assert!(child.iter().all(|gene| (0.0..=4.0).contains(gene)));
+    // a quarter of the range lies beyond either parent
+    assert!(child.iter().any(|&gene| gene < 1.0) && child.iter().any(|&gene| gene > 3.0));
+
+    let child = BlendCrossover::new(0.0).crossover(&mut rng, &parent_a, &parent_b);
+    assert!(child.iter().all(|gene| (1.0..=3.0).contains(gene)));
+
+    // identical parents have nowhere to spread
+    let child = BlendCrossover::new(0.5).crossover(&mut rng, &parent_a, &parent_a);
+    assert_eq!(child, parent_a);
+}
+
+#[test]
+fn test_simulated_binary() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let parent_a = Chromosome::new(vec![1.0; 10_000]);
+    let parent_b = Chromosome::new(vec![3.0; 10_000]);
+
+    let child = SimulatedBinaryCrossover::new(2.0).crossover(&mut rng, &parent_a, &parent_b);
+    let mean = child.iter().sum::<f32>() / child.len() as f32;
+    assert_eq!(child.len(), 10_000);
+    assert!((mean - 2.0).abs() < 0.02, "{mean}");
+
+    // the higher the index, the more children look like their parents
+    let near_parents = |eta: f32, rng: &mut ChaCha8Rng| {
+        let child = SimulatedBinaryCrossover::new(eta).crossover(rng, &parent_a, &parent_b);
+        child.iter().filter(|&&gene| (gene - 1.0).abs() < 0.25 || (gene - 3.0).abs() < 0.25).count()
+    };
+    assert!(near_parents(20.0, &mut rng) > near_parents(2.0, &mut rng));
+
+    let child = SimulatedBinaryCrossover::new(2.0).crossover(&mut rng, &parent_a, &parent_a);
+    assert_eq!(child, parent_a);
+}
+
+#[test]
+fn test_layer_aware() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let (parent_a, parent_b) = crossover_parents(10);
+    let method = LayerAwareCrossover::new([3, 3, 4]);
+
+    for _ in 0..100 {
+        let child = method.crossover(&mut rng, &parent_a, &parent_b);
+        let from_a = from_parent_a(&child);
+
+        assert_eq!(child.len(), 10);
+        assert!(child.iter().enumerate().all(|(idx, &gene)| gene == parent_a[idx] || gene == parent_b[idx]));
+        for block in [0..3, 3..6, 6..10] {
+            assert!(from_a[block.clone()].iter().all(|&gene| gene == from_a[block.start]), "{from_a:?}");
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SelectionError {
+    /// There's nobody to select from
+    EmptyPopulation,
+    /// Individual at given index has a NaN or infinite fitness
+    InvalidFitness(usize),
+    /// Individual at given index has a negative fitness, which
+    /// `FitnessPolicy::Error` doesn't accept
+    NegativeFitness(usize),
+    /// Nobody has a positive fitness, which `FitnessPolicy::Error` doesn't
+    /// accept
+    ZeroFitness,
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyPopulation => write!(f, "got an empty population"),
+            Self::InvalidFitness(idx) => write!(f, "individual #{idx} has an invalid fitness"),
+            Self::NegativeFitness(idx) => write!(f, "individual #{idx} has a negative fitness"),
+            Self::ZeroFitness => write!(f, "nobody has a positive fitness"),
+        }
+    }
+}
+
+impl std::error::Error for SelectionError {}
@@ -0,0 +1,95 @@
+use crate::*;
+
+/// Best chromosomes seen across every generation evolved by a
+/// `GeneticAlgorithm`, fittest first.
+#[derive(Clone, Debug, Default)]
+pub struct HallOfFame {
+    capacity: usize,
+    entries: Vec<HallOfFameEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HallOfFameEntry {
+    pub chromosome: Chromosome,
+    pub fitness: f32,
+    /// Generation the chromosome scored its fitness in, counting from 0
+    pub generation: usize,
+}
+
+impl HallOfFame {
+    /// Keeps at most `capacity` chromosomes.
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Vec::with_capacity(capacity) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn entries(&self) -> &[HallOfFameEntry] {
+        &self.entries
+    }
+
+    pub fn best(&self) -> Option<&HallOfFameEntry> {
+        self.entries.first()
+    }
+
+    /// Enters every individual of `population` fit enough to make it; a
+    /// chromosome already in - e.g. an elite carried over - only moves when
+    /// it scores better than before.
+    pub fn record<I>(&mut self, generation: usize, population: &[I]) where I: Individual {
+        if self.capacity == 0 {
+            return;
+        }
+
+        for individual in population {
+            let fitness = individual.fitness();
+
+            if fitness.is_nan() || !self.entries.is_empty() && self.is_full() && self.entries[self.entries.len() - 1].fitness >= fitness {
+                continue;
+            }
+
+            if let Some(idx) = self.entries.iter().position(|entry| entry.chromosome == *individual.chromosome()) {
+                if self.entries[idx].fitness >= fitness {
+                    continue;
+                }
+                self.entries.remove(idx);
+            }
+
+            let idx = self.entries.partition_point(|entry| entry.fitness >= fitness);
+            self.entries.insert(idx, HallOfFameEntry { chromosome: individual.chromosome().clone(), fitness, generation });
+            self.entries.truncate(self.capacity);
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() >= self.capacity
+    }
+}
+
+#[test]
+fn test_hall_of_fame() {
+    let mut hall_of_fame = HallOfFame::new(3);
+    hall_of_fame.record(0, &[1.0, 5.0, 2.0].map(TestIndividual::new));
+    hall_of_fame.record(1, &[3.0, 0.5, f32::NAN].map(TestIndividual::new));
+
+    let entries = |hall_of_fame: &HallOfFame| -> Vec<(f32, usize)> {
+        hall_of_fame.entries().iter().map(|entry| (entry.fitness, entry.generation)).collect()
+    };
+    assert_eq!(entries(&hall_of_fame), &[(5.0, 0), (3.0, 1), (2.0, 0)]);
+
+    // the same chromosome doesn't take up two places
+    let mut survivor = TestIndividual::new(5.0);
+    survivor.fitness = 4.0;
+    hall_of_fame.record(2, &[survivor.clone()]);
+    assert_eq!(entries(&hall_of_fame), &[(5.0, 0), (3.0, 1), (2.0, 0)]);
+
+    survivor.fitness = 6.0;
+    hall_of_fame.record(3, &[survivor]);
+    assert_eq!(entries(&hall_of_fame), &[(6.0, 3), (3.0, 1), (2.0, 0)]);
+    assert_eq!(hall_of_fame.best().unwrap().chromosome, Chromosome::new(vec![5.0]));
+
+    let mut nobody = HallOfFame::new(0);
+    nobody.record(0, &[TestIndividual::new(1.0)]);
+    assert!(nobody.entries().is_empty());
+}
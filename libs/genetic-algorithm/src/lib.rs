@@ -1,10 +1,25 @@
+pub use self::crossover::*;
+pub use self::error::*;
+pub use self::hall_of_fame::*;
+pub use self::mutation::*;
+pub use self::selection::*;
+
+mod crossover;
+mod error;
+mod hall_of_fame;
+mod mutation;
+mod selection;
+
 use rand::RngCore;
 use rand::Rng;
-use rand::seq::SliceRandom;
 use std::ops::*;
 use serde::{Serialize, Deserialize};
-
-//ooga booga do this: https://setu677.medium.com/how-to-perform-roulette-wheel-and-rank-based-selection-in-a-genetic-algorithm-d0829a37a189
+#[cfg(test)]
+use rand::SeedableRng;
+#[cfg(test)]
+use rand_chacha::ChaCha8Rng;
+#[cfg(test)]
+use std::collections::BTreeMap;
 
 pub trait Individual {
     fn fitness(&self) -> f32;
@@ -12,72 +27,101 @@ pub trait Individual {
     fn create(chromosome: Chromosome) -> Self;
 }
 
-pub trait SelectionMethod {
-    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I where I: Individual;
-}
-
-#[derive(Default)]
-pub struct RouletteWheelSelection;
-
-impl RouletteWheelSelection {
-    pub fn new() -> Self {
-        Self
-    }
-}
-
-impl SelectionMethod for RouletteWheelSelection {
-    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> &'a I 
-    where I: Individual {
-        population
-        .choose_weighted(rng, |individual| individual.fitness())
-        .expect("got an empty population")
-    }
-}
-
 pub struct GeneticAlgorithm<S> {
     selection_method: S,
     crossover_method: Box<dyn CrossoverMethod>,
     mutation_method: Box<dyn MutationMethod>,
+    /// How many of the fittest individuals make it unchanged into the next
+    /// generation
+    elitism: usize,
+    hall_of_fame: HallOfFame,
+    /// Generations evolved so far
+    generation: usize,
 }
 
 impl<S> GeneticAlgorithm<S> where S: SelectionMethod{
     pub fn new(selection_method: S, crossover_method: impl CrossoverMethod + 'static, mutation_method: impl MutationMethod + 'static) -> Self {
-        Self { selection_method, crossover_method: Box::new(crossover_method), mutation_method: Box::new(mutation_method)}
+        Self {
+            selection_method,
+            crossover_method: Box::new(crossover_method),
+            mutation_method: Box::new(mutation_method),
+            elitism: 0,
+            hall_of_fame: HallOfFame::default(),
+            generation: 0,
+        }
     }
 
-    pub fn evolve<I>(&self, rng: &mut dyn RngCore, population: &[I]) -> (Vec<I>, Statistics) where I: Individual{
-        assert!(!population.is_empty());
+    /// Copies the `count` fittest individuals of every generation into the
+    /// next one unchanged, so that the best one found so far can't be lost.
+    pub fn with_elitism(self, count: usize) -> Self {
+        Self { elitism: count, ..self }
+    }
 
+    /// Keeps track of the `capacity` best chromosomes ever evolved.
+    pub fn with_hall_of_fame(self, capacity: usize) -> Self {
+        Self { hall_of_fame: HallOfFame::new(capacity), ..self }
+    }
+
+    pub fn hall_of_fame(&self) -> &HallOfFame {
+        &self.hall_of_fame
+    }
+
+    /// Breeds a generation of the same size; fails, leaving `population`
+    /// to the caller, when the selection method can't pick parents out of it.
+    pub fn evolve<I>(&mut self, rng: &mut dyn RngCore, population: &[I]) -> Result<(Vec<I>, Statistics), SelectionError> where I: Individual{
+        if population.is_empty() {
+            return Err(SelectionError::EmptyPopulation);
+        }
+
+        // Whether NaN fitness is acceptable is up to the selection method;
+        // either way, such individuals never count among the elites
+        let mut elites: Vec<&I> = population.iter().filter(|individual| !individual.fitness().is_nan()).collect();
+        elites.sort_by(|a, b| b.fitness().total_cmp(&a.fitness()));
+        elites.truncate(self.elitism);
+
+        let parents = self.selection_method.select_many(rng, population, 2 * (population.len() - elites.len()))?;
         let stats = Statistics::new(population);
-        let new_population = (0..population.len())
-        .map(|_| {
-            let parent1 = self.selection_method.select(rng, population).chromosome();
-            let parent2 = self.selection_method.select(rng, population).chromosome();
+        self.hall_of_fame.record(self.generation, population);
+        self.generation += 1;
+
+        let children = parents
+        .chunks_exact(2)
+        .map(|parents| {
+            let parent1 = parents[0].chromosome();
+            let parent2 = parents[1].chromosome();
             let mut child = self.crossover_method.crossover(rng, parent1, parent2);
             self.mutation_method.mutate(rng, &mut child);
             
-            return I::create(child);
-        })
+            I::create(child)
+        });
+        let new_population = elites
+        .into_iter()
+        .map(|elite| I::create(elite.chromosome().clone()))
+        .chain(children)
         .collect();
 
-        (new_population, stats)
+        Ok((new_population, stats))
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Chromosome {
     genes: Vec<f32>,
 }
 
 impl Chromosome {
     pub fn new(genes: Vec<f32>) -> Self {
-        Self{genes: genes}
+        Self{genes}
     }
 
     pub fn len(&self) -> usize {
         self.genes.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.genes.is_empty()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &f32> {
         self.genes.iter()
     }
@@ -112,75 +156,6 @@ impl IntoIterator for Chromosome {
     }
 }
 
-pub trait CrossoverMethod {
-    fn crossover(
-        &self,
-        rng: &mut dyn RngCore,
-        parent_a: &Chromosome,
-        parent_b: &Chromosome,
-    ) -> Chromosome;
-}
-
-#[derive(Clone, Debug, Default)]
-pub struct UniformCrossover;
-
-impl UniformCrossover {
-    pub fn new() -> Self {
-        Self
-    }
-}
-
-impl CrossoverMethod for UniformCrossover {
-    fn crossover(&self, rng: &mut dyn RngCore, parent_a: &Chromosome, parent_b: &Chromosome) -> Chromosome {
-        assert_eq!(parent_a.len(), parent_b.len());
-
-        let parent_a = parent_a.iter();
-        let parent_b = parent_b.iter();
-    
-        parent_a
-            .zip(parent_b)
-            .map(|(&a, &b)| if rng.gen_bool(0.5) { a } else { b })
-            .collect()
-    }
-}
-
-pub trait MutationMethod {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome);
-}
-
-#[derive(Clone, Debug)]
-pub struct GaussianMutation {
-    /// Probability of changing a gene:
-    /// - 0.0 = no genes will be touched
-    /// - 1.0 = all genes will be touched
-    chance: f32,
-
-    /// Magnitude of that change:
-    /// - 0.0 = touched genes will not be modified
-    /// - 3.0 = touched genes will be += or -= by at most 3.0
-    coeff: f32,
-}
-
-impl GaussianMutation {
-    pub fn new(chance: f32, coeff: f32) -> Self {
-        assert!(chance >= 0.0 && chance <= 1.0);
-
-        Self { chance, coeff }
-    }
-}
-
-impl MutationMethod for GaussianMutation {
-    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
-        for gene in child.iter_mut() {
-            let sign = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
-
-            if rng.gen_bool(self.chance as _) {
-                *gene += sign * self.coeff * rng.gen::<f32>();
-            }
-        }
-    }
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Statistics {
     min_fitness: f32,
@@ -190,23 +165,29 @@ pub struct Statistics {
 
 impl Statistics {
     fn new<I>(population: &[I]) -> Self where I: Individual{
-        assert!(!population.is_empty());
-        let mut min_fitness = population[0].fitness();
-        let mut max_fitness = min_fitness;
-        let mut sum_fitness = 0.0;
+        Self::from_fitness(population.iter().map(Individual::fitness))
+    }
 
-        for individual in population {
-            let fitness = individual.fitness();
+    /// Summarizes a population given just its fitness values, for
+    /// populations evolved outside of `GeneticAlgorithm`.
+    pub fn from_fitness(fitness: impl IntoIterator<Item = f32>) -> Self {
+        let mut fitness = fitness.into_iter();
+        let mut min_fitness = fitness.next().expect("got an empty population");
+        let mut max_fitness = min_fitness;
+        let mut sum_fitness = min_fitness;
+        let mut count = 1;
 
+        for fitness in fitness {
             min_fitness = min_fitness.min(fitness);
             max_fitness = max_fitness.max(fitness);
             sum_fitness += fitness;
+            count += 1;
         }
 
         Self {
             min_fitness,
             max_fitness,
-            avg_fitness: sum_fitness / (population.len() as f32),
+            avg_fitness: sum_fitness / (count as f32),
         }
     }
 
@@ -221,4 +202,88 @@ impl Statistics {
     pub fn avg_fitness(&self) -> f32 {
         self.avg_fitness
     }
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug)]
+struct TestIndividual {
+    fitness: f32,
+    chromosome: Chromosome,
+}
+
+#[cfg(test)]
+impl TestIndividual {
+    fn new(fitness: f32) -> Self {
+        Self { fitness, chromosome: Chromosome::new(vec![fitness]) }
+    }
+}
+
+#[cfg(test)]
+impl Individual for TestIndividual {
+    fn fitness(&self) -> f32 {
+        self.fitness
+    }
+
+    fn chromosome(&self) -> &Chromosome {
+        &self.chromosome
+    }
+
+    fn create(chromosome: Chromosome) -> Self {
+        Self { fitness: 0.0, chromosome }
+    }
+}
+
+/// Always picks the first individual, whatever its fitness.
+#[cfg(test)]
+struct FirstSelection;
+
+#[cfg(test)]
+impl SelectionMethod for FirstSelection {
+    fn select<'a, I>(&self, _rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        population.first().ok_or(SelectionError::EmptyPopulation)
+    }
+}
+
+#[test]
+fn test_evolve_reports_selection_errors() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut ga = GeneticAlgorithm::new(RouletteWheelSelection::new(), UniformCrossover, GaussianMutation::new(0.5, 0.1));
+
+    let empty: Vec<TestIndividual> = Vec::new();
+    assert_eq!(ga.evolve(&mut rng, &empty).unwrap_err(), SelectionError::EmptyPopulation);
+
+    let starved = vec![TestIndividual::new(0.0); 4];
+    assert_eq!(ga.evolve(&mut rng, &starved).unwrap().0.len(), 4);
+}
+
+#[test]
+fn test_elitism() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut ga = GeneticAlgorithm::new(RouletteWheelSelection::new(), UniformCrossover, GaussianMutation::new(1.0, 0.1))
+        .with_elitism(2)
+        .with_hall_of_fame(3);
+    let population: Vec<_> = [2.0, 1.0, 4.0, 3.0].into_iter().map(TestIndividual::new).collect();
+
+    let (evolved, _) = ga.evolve(&mut rng, &population).unwrap();
+    let genes: Vec<f32> = evolved.iter().map(|individual| individual.chromosome()[0]).collect();
+    assert_eq!(&genes[..2], &[4.0, 3.0]);
+    assert!(genes[2..].iter().all(|gene| gene.fract() != 0.0), "{genes:?}");
+
+    // everyone of the next generation scores 0
+    ga.evolve(&mut rng, &evolved).unwrap();
+    let hall_of_fame: Vec<_> = ga.hall_of_fame().entries().iter().map(|entry| (entry.fitness, entry.generation)).collect();
+    assert_eq!(hall_of_fame, &[(4.0, 0), (3.0, 0), (2.0, 0)]);
+
+    // elitism beyond the population keeps everyone
+    let mut ga = GeneticAlgorithm::new(RouletteWheelSelection::new(), UniformCrossover, GaussianMutation::new(1.0, 0.1)).with_elitism(10);
+    let (evolved, _) = ga.evolve(&mut rng, &population).unwrap();
+    assert_eq!(evolved.len(), 4);
+
+    // elites are skipped, rather than rejected, when the selection method
+    // tolerates NaN fitness
+    let mut ga = GeneticAlgorithm::new(FirstSelection, UniformCrossover, GaussianMutation::new(0.0, 0.1)).with_elitism(2);
+    let population: Vec<_> = [1.0, f32::NAN, 3.0].into_iter().map(TestIndividual::new).collect();
+    let (evolved, _) = ga.evolve(&mut rng, &population).unwrap();
+    let genes: Vec<f32> = evolved.iter().map(|individual| individual.chromosome()[0]).collect();
+    assert_eq!(genes, &[3.0, 1.0, 1.0]);
 }
\ No newline at end of file
@@ -0,0 +1,260 @@
+use crate::*;
+use rand_distr::{Distribution, Normal};
+use std::ops::RangeInclusive;
+
+pub trait MutationMethod {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome);
+}
+
+#[derive(Clone, Debug)]
+pub struct GaussianMutation {
+    /// Probability of changing a gene:
+    /// - 0.0 = no genes will be touched
+    /// - 1.0 = all genes will be touched
+    chance: f32,
+
+    /// Standard deviation of that change:
+    /// - 0.0 = touched genes will not be modified
+    /// - 3.0 = touched genes will be += or -= by less than 3.0 about two
+    ///   times out of three
+    coeff: f32,
+}
+
+impl GaussianMutation {
+    pub fn new(chance: f32, coeff: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(coeff.is_finite() && coeff >= 0.0);
+
+        Self { chance, coeff }
+    }
+}
+
+impl MutationMethod for GaussianMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        let normal = Normal::new(0.0, self.coeff).expect("standard deviation was checked");
+
+        for gene in child.iter_mut() {
+            if rng.gen_bool(self.chance as _) {
+                *gene += normal.sample(rng);
+            }
+        }
+    }
+}
+
+/// Deb's polynomial mutation for genes within bounds: touched genes move
+/// to anywhere within the bounds, but mostly stay close to where they were.
+#[derive(Clone, Debug)]
+pub struct PolynomialMutation {
+    chance: f32,
+    /// Distribution index; the higher, the smaller the changes (usually
+    /// somewhere within `20.0..=100.0`)
+    eta: f32,
+    min: f32,
+    max: f32,
+}
+
+impl PolynomialMutation {
+    pub fn new(chance: f32, eta: f32, bounds: RangeInclusive<f32>) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(eta >= 0.0);
+        assert!(bounds.start() < bounds.end());
+
+        Self { chance, eta, min: *bounds.start(), max: *bounds.end() }
+    }
+}
+
+impl MutationMethod for PolynomialMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        let span = self.max - self.min;
+        let power = 1.0 / (self.eta + 1.0);
+
+        for gene in child.iter_mut() {
+            if !rng.gen_bool(self.chance as _) {
+                continue;
+            }
+
+            let x = gene.clamp(self.min, self.max);
+            let u: f32 = rng.gen();
+
+            // perturbations are scaled by the distance to the bound they
+            // head to, so that they never cross it
+            let delta = if u < 0.5 {
+                let room = 1.0 - (x - self.min) / span;
+                (2.0 * u + (1.0 - 2.0 * u) * room.powf(self.eta + 1.0)).powf(power) - 1.0
+            } else {
+                let room = 1.0 - (self.max - x) / span;
+                1.0 - (2.0 * (1.0 - u) + 2.0 * (u - 0.5) * room.powf(self.eta + 1.0)).powf(power)
+            };
+
+            *gene = (x + delta * span).clamp(self.min, self.max);
+        }
+    }
+}
+
+/// Replaces touched genes with values drawn uniformly within bounds.
+#[derive(Clone, Debug)]
+pub struct UniformResetMutation {
+    chance: f32,
+    bounds: RangeInclusive<f32>,
+}
+
+impl UniformResetMutation {
+    pub fn new(chance: f32, bounds: RangeInclusive<f32>) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(bounds.start() <= bounds.end());
+
+        Self { chance, bounds }
+    }
+}
+
+impl MutationMethod for UniformResetMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        for gene in child.iter_mut() {
+            if rng.gen_bool(self.chance as _) {
+                *gene = rng.gen_range(self.bounds.clone());
+            }
+        }
+    }
+}
+
+/// Nudges touched genes by at most `step` either way, uniformly.
+#[derive(Clone, Debug)]
+pub struct CreepMutation {
+    chance: f32,
+    step: f32,
+}
+
+impl CreepMutation {
+    pub fn new(chance: f32, step: f32) -> Self {
+        assert!((0.0..=1.0).contains(&chance));
+        assert!(step.is_finite() && step >= 0.0);
+
+        Self { chance, step }
+    }
+}
+
+impl MutationMethod for CreepMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        for gene in child.iter_mut() {
+            if rng.gen_bool(self.chance as _) {
+                *gene += rng.gen_range(-self.step..=self.step);
+            }
+        }
+    }
+}
+
+/// Runs several methods one after another, each of them on a given
+/// fraction of children, e.g. frequent small nudges and rare resets.
+#[derive(Default)]
+pub struct CompositeMutation {
+    methods: Vec<(f32, Box<dyn MutationMethod>)>,
+}
+
+impl CompositeMutation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `method`, applied to a child with given probability.
+    pub fn with(mut self, probability: f32, method: impl MutationMethod + 'static) -> Self {
+        assert!((0.0..=1.0).contains(&probability));
+
+        self.methods.push((probability, Box::new(method)));
+        self
+    }
+}
+
+impl MutationMethod for CompositeMutation {
+    fn mutate(&self, rng: &mut dyn RngCore, child: &mut Chromosome) {
+        for (probability, method) in &self.methods {
+            if rng.gen_bool(*probability as _) {
+                method.mutate(rng, child);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn mutated(method: &dyn MutationMethod, genes: Vec<f32>) -> Vec<f32> {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let mut child = Chromosome::new(genes);
+    method.mutate(&mut rng, &mut child);
+    child.into_iter().collect()
+}
+
+#[cfg(test)]
+fn mean_and_deviation(genes: &[f32]) -> (f32, f32) {
+    let mean = genes.iter().sum::<f32>() / genes.len() as f32;
+    let variance = genes.iter().map(|gene| (gene - mean).powi(2)).sum::<f32>() / genes.len() as f32;
+    (mean, variance.sqrt())
+}
+
+#[test]
+fn test_gaussian() {
+    let genes = mutated(&GaussianMutation::new(1.0, 0.5), vec![1.0; 10_000]);
+    let (mean, deviation) = mean_and_deviation(&genes);
+    assert!((mean - 1.0).abs() < 0.02, "{mean}");
+    assert!((deviation - 0.5).abs() < 0.02, "{deviation}");
+
+    // about 68% within one standard deviation, which a uniform change
+    // wouldn't give
+    let within = genes.iter().filter(|gene| (*gene - 1.0).abs() < 0.5).count();
+    assert!((6700..6900).contains(&within), "{within}");
+
+    let genes = mutated(&GaussianMutation::new(0.1, 0.5), vec![1.0; 10_000]);
+    let touched = genes.iter().filter(|&&gene| gene != 1.0).count();
+    assert!((900..1100).contains(&touched), "{touched}");
+}
+
+#[test]
+fn test_polynomial() {
+    let genes = mutated(&PolynomialMutation::new(1.0, 20.0, -1.0..=1.0), vec![0.0; 10_000]);
+    let (mean, deviation) = mean_and_deviation(&genes);
+    assert!(genes.iter().all(|gene| (-1.0..=1.0).contains(gene)));
+    assert!(mean.abs() < 0.01, "{mean}");
+    assert!(deviation > 0.01 && deviation < 0.2, "{deviation}");
+
+    // genes at a bound can only move away from it
+    let genes = mutated(&PolynomialMutation::new(1.0, 20.0, -1.0..=1.0), vec![1.0; 1000]);
+    assert!(genes.iter().all(|gene| (-1.0..=1.0).contains(gene)));
+    assert!(genes.iter().any(|&gene| gene < 1.0));
+}
+
+#[test]
+fn test_uniform_reset() {
+    let genes = mutated(&UniformResetMutation::new(1.0, 2.0..=4.0), vec![0.0; 10_000]);
+    let (mean, deviation) = mean_and_deviation(&genes);
+    assert!(genes.iter().all(|gene| (2.0..=4.0).contains(gene)));
+    assert!((mean - 3.0).abs() < 0.02, "{mean}");
+    // a uniform distribution over a range of 2.0 deviates by 2.0 / sqrt(12)
+    assert!((deviation - 0.577).abs() < 0.02, "{deviation}");
+}
+
+#[test]
+fn test_creep() {
+    let genes = mutated(&CreepMutation::new(1.0, 0.1), vec![5.0; 10_000]);
+    let (mean, _) = mean_and_deviation(&genes);
+    assert!(genes.iter().all(|gene| (gene - 5.0).abs() <= 0.1 + f32::EPSILON * 5.0));
+    assert!((mean - 5.0).abs() < 0.005, "{mean}");
+}
+
+#[test]
+fn test_composite() {
+    let method = CompositeMutation::new()
+        .with(1.0, UniformResetMutation::new(1.0, 10.0..=10.0))
+        .with(0.0, UniformResetMutation::new(1.0, 20.0..=20.0))
+        .with(1.0, CreepMutation::new(1.0, 0.0));
+    assert_eq!(mutated(&method, vec![0.0; 3]), &[10.0; 3]);
+
+    // each method is applied to the whole child or not at all
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let method = CompositeMutation::new().with(0.5, UniformResetMutation::new(1.0, 1.0..=1.0));
+    let mut reset = 0;
+    for _ in 0..1000 {
+        let mut child = Chromosome::new(vec![0.0; 4]);
+        method.mutate(&mut rng, &mut child);
+        assert!(child.iter().all(|&gene| gene == child[0]));
+        reset += (child[0] == 1.0) as usize;
+    }
+    assert!((450..550).contains(&reset), "{reset}");
+}
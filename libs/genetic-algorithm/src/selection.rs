@@ -0,0 +1,440 @@
+use crate::*;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use std::cmp::Ordering;
+
+pub trait SelectionMethod {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual;
+
+    /// Picks `count` individuals at once, e.g. the parents of a whole
+    /// generation; methods that can spread their picks better than
+    /// independent draws (or that need to sort the population first)
+    /// override this.
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], count: usize) -> Result<Vec<&'a I>, SelectionError> where I: Individual {
+        (0..count).map(|_| self.select(rng, population)).collect()
+    }
+}
+
+/// What fitness proportionate methods do about fitness that can't serve as
+/// a weight - e.g. when every animal of an early generation starved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitnessPolicy {
+    /// Negative fitness counts as zero, and when nobody is left with a
+    /// positive fitness, everyone is equally likely to be picked
+    #[default]
+    Uniform,
+    /// Negative fitness shifts everyone's fitness up, so that the least fit
+    /// individual ends up with zero; then as `Uniform`
+    Offset,
+    /// Negative fitness, or nobody with a positive one, is an error
+    Error,
+}
+
+impl FitnessPolicy {
+    /// Weights of `population` for a fitness proportionate selection.
+    fn weights<I: Individual>(self, population: &[I]) -> Result<Vec<f32>, SelectionError> {
+        validate(population)?;
+
+        let fitness = population.iter().map(Individual::fitness);
+        let lowest = fitness.clone().fold(0.0, f32::min);
+
+        let mut weights: Vec<f32> = match self {
+            Self::Uniform => fitness.map(|fitness| fitness.max(0.0)).collect(),
+            Self::Offset => fitness.map(|fitness| fitness - lowest).collect(),
+            Self::Error => match fitness.clone().position(|fitness| fitness < 0.0) {
+                Some(idx) => return Err(SelectionError::NegativeFitness(idx)),
+                None => fitness.collect(),
+            },
+        };
+
+        if weights.iter().all(|&weight| weight == 0.0) {
+            if self == Self::Error {
+                return Err(SelectionError::ZeroFitness);
+            }
+            weights.fill(1.0);
+        }
+
+        Ok(weights)
+    }
+}
+
+/// Fitness proportionate selection: every individual is picked with
+/// probability `fitness / total fitness`.
+#[derive(Clone, Debug, Default)]
+pub struct RouletteWheelSelection {
+    policy: FitnessPolicy,
+}
+
+impl RouletteWheelSelection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(self, policy: FitnessPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl SelectionMethod for RouletteWheelSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        Ok(self.select_many(rng, population, 1)?[0])
+    }
+
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], count: usize) -> Result<Vec<&'a I>, SelectionError> where I: Individual {
+        let weights = self.policy.weights(population)?;
+        let distribution = WeightedIndex::new(weights).expect("weights are finite and not all zero");
+
+        Ok((0..count).map(|_| &population[distribution.sample(rng)]).collect())
+    }
+}
+
+/// Picks the fittest of `size` randomly drawn contestants; the larger the
+/// tournament, the lower the chances of weak individuals.
+#[derive(Clone, Debug)]
+pub struct TournamentSelection {
+    size: usize,
+    /// Whether the same individual can enter a tournament more than once
+    replacement: bool,
+}
+
+impl TournamentSelection {
+    /// Tournaments of `size` contestants, drawn with replacement.
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0);
+
+        Self { size, replacement: true }
+    }
+
+    /// Draws distinct contestants instead; tournaments larger than the
+    /// population then hold just the whole population.
+    pub fn without_replacement(self) -> Self {
+        Self { replacement: false, ..self }
+    }
+}
+
+impl SelectionMethod for TournamentSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        validate(population)?;
+
+        let winner = if self.replacement {
+            (0..self.size).map(|_| &population[rng.gen_range(0..population.len())]).max_by(by_fitness)
+        } else {
+            population.choose_multiple(rng, self.size).max_by(by_fitness)
+        };
+
+        Ok(winner.expect("tournaments have contestants"))
+    }
+}
+
+/// How strongly `RankSelection` favours individuals ranked higher.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RankPressure {
+    /// Chances grow linearly with the rank, the best individual being
+    /// picked this many times (within `1.0..=2.0`) as often as an average
+    /// one, and the worst `2.0 - pressure` times as often
+    Linear(f32),
+    /// Every individual is this many times (within `0.0..=1.0`) as likely
+    /// to be picked as the one ranked right above it
+    Exponential(f32),
+}
+
+/// Picks individuals with chances depending on their rank rather than
+/// fitness, so that a single outstanding individual doesn't take over the
+/// population and small differences still matter.
+#[derive(Clone, Debug)]
+pub struct RankSelection {
+    pressure: RankPressure,
+}
+
+impl RankSelection {
+    pub fn new(pressure: RankPressure) -> Self {
+        match pressure {
+            RankPressure::Linear(pressure) => assert!((1.0..=2.0).contains(&pressure)),
+            RankPressure::Exponential(base) => assert!(base > 0.0 && base <= 1.0),
+        }
+
+        Self { pressure }
+    }
+
+    /// Weight of the individual at `rank`, counting from the worst one as 0.
+    fn weight(&self, rank: usize, population: usize) -> f32 {
+        match self.pressure {
+            RankPressure::Linear(_) if population == 1 => 1.0,
+            RankPressure::Linear(pressure) => {
+                2.0 - pressure + 2.0 * (pressure - 1.0) * rank as f32 / (population - 1) as f32
+            }
+            RankPressure::Exponential(base) => base.powi((population - 1 - rank) as i32),
+        }
+    }
+}
+
+impl SelectionMethod for RankSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        Ok(self.select_many(rng, population, 1)?[0])
+    }
+
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], count: usize) -> Result<Vec<&'a I>, SelectionError> where I: Individual {
+        let ranked = ranked(population)?;
+        let weights = (0..ranked.len()).map(|rank| self.weight(rank, ranked.len()));
+        let distribution = WeightedIndex::new(weights).expect("ranks have positive weights");
+
+        Ok((0..count).map(|_| ranked[distribution.sample(rng)]).collect())
+    }
+}
+
+/// Picks uniformly among the given fraction of the fittest individuals;
+/// the rest never get to be parents.
+#[derive(Clone, Debug)]
+pub struct TruncationSelection {
+    fraction: f32,
+}
+
+impl TruncationSelection {
+    /// Keeps `fraction` (within `0.0..=1.0`) of the population, but always
+    /// at least a single individual.
+    pub fn new(fraction: f32) -> Self {
+        assert!(fraction > 0.0 && fraction <= 1.0);
+
+        Self { fraction }
+    }
+}
+
+impl SelectionMethod for TruncationSelection {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        Ok(self.select_many(rng, population, 1)?[0])
+    }
+
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], count: usize) -> Result<Vec<&'a I>, SelectionError> where I: Individual {
+        let ranked = ranked(population)?;
+        let kept = ((population.len() as f32 * self.fraction).ceil() as usize).clamp(1, population.len());
+        let fittest = &ranked[ranked.len() - kept..];
+
+        Ok((0..count).map(|_| fittest[rng.gen_range(0..kept)]).collect())
+    }
+}
+
+/// Fitness proportionate like `RouletteWheelSelection`, but picks many
+/// individuals through evenly spaced pointers spun together, so that each
+/// one is picked within one of its expected number of times.
+#[derive(Clone, Debug, Default)]
+pub struct StochasticUniversalSampling {
+    policy: FitnessPolicy,
+}
+
+impl StochasticUniversalSampling {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(self, policy: FitnessPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+impl SelectionMethod for StochasticUniversalSampling {
+    fn select<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I]) -> Result<&'a I, SelectionError> where I: Individual {
+        Ok(self.select_many(rng, population, 1)?[0])
+    }
+
+    fn select_many<'a, I>(&self, rng: &mut dyn RngCore, population: &'a [I], count: usize) -> Result<Vec<&'a I>, SelectionError> where I: Individual {
+        let weights = self.policy.weights(population)?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        // The policy leaves at least one positive weight, but in `f32` tiny
+        // weights could still make the spacing round down to zero (and huge
+        // ones overflow the total)
+        let total: f64 = weights.iter().map(|&weight| weight as f64).sum();
+        let spacing = total / count as f64;
+        let start = rng.gen_range(0.0..spacing);
+
+        let mut selected = Vec::with_capacity(count);
+        let mut individuals = population.iter().zip(&weights).skip(1);
+        let (mut current, mut reached) = (&population[0], weights[0] as f64);
+
+        for pointer in (0..count).map(|n| start + n as f64 * spacing) {
+            // (rounding errors could make the last pointer overshoot)
+            while pointer >= reached {
+                let Some((next, &weight)) = individuals.next() else {
+                    break;
+                };
+                current = next;
+                reached += weight as f64;
+            }
+
+            selected.push(current);
+        }
+
+        // picks come out in the order of the population, which would make
+        // neighbours mate with each other
+        selected.shuffle(rng);
+        Ok(selected)
+    }
+}
+
+fn by_fitness<I: Individual>(a: &&I, b: &&I) -> Ordering {
+    a.fitness().total_cmp(&b.fitness())
+}
+
+/// Checks that there's anybody to pick from, all with a meaningful fitness.
+fn validate<I: Individual>(population: &[I]) -> Result<(), SelectionError> {
+    if population.is_empty() {
+        return Err(SelectionError::EmptyPopulation);
+    }
+
+    match population.iter().position(|individual| !individual.fitness().is_finite()) {
+        Some(idx) => Err(SelectionError::InvalidFitness(idx)),
+        None => Ok(()),
+    }
+}
+
+/// `population` sorted from the least to the most fit.
+pub(crate) fn ranked<I: Individual>(population: &[I]) -> Result<Vec<&I>, SelectionError> {
+    validate(population)?;
+
+    let mut ranked: Vec<&I> = population.iter().collect();
+    ranked.sort_by(by_fitness);
+    Ok(ranked)
+}
+
+#[cfg(test)]
+fn histogram<'a>(selected: impl IntoIterator<Item = &'a TestIndividual>) -> BTreeMap<i32, usize> {
+    let mut histogram = BTreeMap::new();
+    for individual in selected {
+        *histogram.entry(individual.fitness() as i32).or_default() += 1;
+    }
+    histogram
+}
+
+#[cfg(test)]
+fn selection_population() -> Vec<TestIndividual> {
+    [2.0, 1.0, 4.0, 3.0].into_iter().map(TestIndividual::new).collect()
+}
+
+#[test]
+fn test_roulette_wheel() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let population = selection_population();
+
+    let selected = (0..1000).map(|_| RouletteWheelSelection::new().select(&mut rng, &population).unwrap());
+    assert_eq!(histogram(selected), BTreeMap::from([(1, 98), (2, 202), (3, 278), (4, 422)]));
+}
+
+#[test]
+fn test_tournament() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let population = selection_population();
+
+    let selected = (0..1000).map(|_| TournamentSelection::new(2).select(&mut rng, &population).unwrap());
+    assert_eq!(histogram(selected), BTreeMap::from([(1, 75), (2, 177), (3, 300), (4, 448)]));
+
+    let selected = (0..1000).map(|_| TournamentSelection::new(2).without_replacement().select(&mut rng, &population).unwrap());
+    assert_eq!(histogram(selected), BTreeMap::from([(2, 164), (3, 347), (4, 489)]));
+
+    // everyone enters, so the best always wins
+    let selection = TournamentSelection::new(10).without_replacement();
+    assert!((0..100).all(|_| selection.select(&mut rng, &population).unwrap().fitness() == 4.0));
+}
+
+#[test]
+fn test_rank() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let population = selection_population();
+
+    let selected = RankSelection::new(RankPressure::Linear(1.5)).select_many(&mut rng, &population, 1000).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(1, 134), (2, 193), (3, 310), (4, 363)]));
+
+    // the worst individual gets no chance at the highest linear pressure
+    let selected = RankSelection::new(RankPressure::Linear(2.0)).select_many(&mut rng, &population, 1000).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(2, 161), (3, 338), (4, 501)]));
+
+    let selected = RankSelection::new(RankPressure::Exponential(0.5)).select_many(&mut rng, &population, 1000).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(1, 64), (2, 131), (3, 258), (4, 547)]));
+
+    // ... while the lowest makes it uniform
+    let selection = RankSelection::new(RankPressure::Linear(1.0));
+    assert!((0..4).all(|rank| selection.weight(rank, 4) == 1.0));
+}
+
+#[test]
+fn test_truncation() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let population = selection_population();
+
+    let selected = TruncationSelection::new(0.5).select_many(&mut rng, &population, 1000).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(3, 490), (4, 510)]));
+
+    // always keeps someone
+    let selected = TruncationSelection::new(0.01).select_many(&mut rng, &population, 10).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(4, 10)]));
+}
+
+#[test]
+fn test_stochastic_universal_sampling() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let population = selection_population();
+
+    // total fitness is 10, so every individual gets exactly its share
+    let selected = StochasticUniversalSampling::new().select_many(&mut rng, &population, 1000).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(1, 100), (2, 200), (3, 300), (4, 400)]));
+
+    let selected = StochasticUniversalSampling::new().select_many(&mut rng, &population, 7).unwrap();
+    for (fitness, count) in histogram(selected) {
+        let expected = fitness as f32 * 0.7;
+        assert!((count as f32 - expected).abs() < 1.0, "{fitness}: {count}");
+    }
+}
+
+#[test]
+fn test_fitness_policies() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let starved: Vec<_> = [0.0, 0.0, 0.0].into_iter().map(TestIndividual::new).collect();
+    let mixed: Vec<_> = [-1.0, 1.0, 3.0].into_iter().map(TestIndividual::new).collect();
+
+    assert_eq!(FitnessPolicy::Uniform.weights(&starved), Ok(vec![1.0, 1.0, 1.0]));
+    assert_eq!(FitnessPolicy::Uniform.weights(&mixed), Ok(vec![0.0, 1.0, 3.0]));
+    assert_eq!(FitnessPolicy::Offset.weights(&starved), Ok(vec![1.0, 1.0, 1.0]));
+    assert_eq!(FitnessPolicy::Offset.weights(&mixed), Ok(vec![0.0, 2.0, 4.0]));
+    assert_eq!(FitnessPolicy::Offset.weights(&selection_population()), Ok(vec![2.0, 1.0, 4.0, 3.0]));
+    assert_eq!(FitnessPolicy::Error.weights(&starved), Err(SelectionError::ZeroFitness));
+    assert_eq!(FitnessPolicy::Error.weights(&mixed), Err(SelectionError::NegativeFitness(0)));
+
+    // starved generations still get to breed
+    let selected = RouletteWheelSelection::new().select_many(&mut rng, &starved, 30).unwrap();
+    assert_eq!(selected.len(), 30);
+    let selected = StochasticUniversalSampling::new().select_many(&mut rng, &starved, 30).unwrap();
+    assert_eq!(histogram(selected), BTreeMap::from([(0, 30)]));
+
+    let selection = RouletteWheelSelection::new().with_policy(FitnessPolicy::Error);
+    assert_eq!(selection.select(&mut rng, &starved).unwrap_err(), SelectionError::ZeroFitness);
+    let selection = StochasticUniversalSampling::new().with_policy(FitnessPolicy::Error);
+    assert_eq!(selection.select_many(&mut rng, &starved, 30).unwrap_err(), SelectionError::ZeroFitness);
+
+    // fitness this low would round the pointers' spacing down to zero in `f32`
+    let tiny: Vec<_> = [1e-45, 0.0, 2e-45].into_iter().map(TestIndividual::new).collect();
+    let selected = StochasticUniversalSampling::new().select_many(&mut rng, &tiny, 30).unwrap();
+    assert_eq!(selected.len(), 30);
+    assert!(selected.iter().all(|individual| individual.fitness() > 0.0));
+}
+
+#[test]
+fn test_invalid_populations() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let empty: Vec<TestIndividual> = Vec::new();
+    let broken: Vec<_> = [1.0, f32::NAN].into_iter().map(TestIndividual::new).collect();
+
+    assert_eq!(RouletteWheelSelection::new().select(&mut rng, &empty).unwrap_err(), SelectionError::EmptyPopulation);
+    assert_eq!(TournamentSelection::new(2).select(&mut rng, &empty).unwrap_err(), SelectionError::EmptyPopulation);
+    assert_eq!(RouletteWheelSelection::new().select(&mut rng, &broken).unwrap_err(), SelectionError::InvalidFitness(1));
+    assert_eq!(TournamentSelection::new(2).select(&mut rng, &broken).unwrap_err(), SelectionError::InvalidFitness(1));
+    assert_eq!(
+        RankSelection::new(RankPressure::Linear(1.5)).select(&mut rng, &broken).unwrap_err(),
+        SelectionError::InvalidFitness(1)
+    );
+    assert_eq!(TruncationSelection::new(0.5).select(&mut rng, &broken).unwrap_err(), SelectionError::InvalidFitness(1));
+    assert_eq!(
+        StochasticUniversalSampling::new().select(&mut rng, &broken).unwrap_err(),
+        SelectionError::InvalidFitness(1)
+    );
+}
@@ -0,0 +1,77 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Non-linearity applied to every neuron's weighted sum within a layer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Activation {
+    #[default]
+    ReLU,
+    /// ReLU that lets negative sums through, scaled by the given slope
+    LeakyReLU(f32),
+    Sigmoid,
+    Tanh,
+    Identity,
+    Softsign,
+}
+
+impl Activation {
+    pub fn apply<T: Scalar>(self, x: T) -> T {
+        match self {
+            Self::ReLU => x.max(T::ZERO),
+            Self::LeakyReLU(slope) => if x > T::ZERO { x } else { T::from_f64(slope as f64) * x },
+            Self::Sigmoid => T::ONE / (T::ONE + (-x).exp()),
+            Self::Tanh => x.tanh(),
+            Self::Identity => x,
+            Self::Softsign => x / (T::ONE + x.abs()),
+        }
+    }
+
+    /// Slope of `apply()` at `x`, where `y` is `apply(x)` - passed in as
+    /// well, since some derivatives are cheapest expressed through it.
+    pub fn derivative<T: Scalar>(self, x: T, y: T) -> T {
+        match self {
+            Self::ReLU => if x > T::ZERO { T::ONE } else { T::ZERO },
+            Self::LeakyReLU(slope) => if x > T::ZERO { T::ONE } else { T::from_f64(slope as f64) },
+            Self::Sigmoid => y * (T::ONE - y),
+            Self::Tanh => T::ONE - y * y,
+            Self::Identity => T::ONE,
+            Self::Softsign => {
+                let denominator = T::ONE + x.abs();
+                T::ONE / (denominator * denominator)
+            }
+        }
+    }
+}
+
+#[test]
+fn activations() {
+    approx::assert_relative_eq!(Activation::ReLU.apply(-2.0f32), 0.0);
+    approx::assert_relative_eq!(Activation::ReLU.apply(2.0f32), 2.0);
+    approx::assert_relative_eq!(Activation::LeakyReLU(0.1).apply(-2.0f32), -0.2);
+    approx::assert_relative_eq!(Activation::LeakyReLU(0.1).apply(2.0f32), 2.0);
+    approx::assert_relative_eq!(Activation::Sigmoid.apply(0.0f32), 0.5);
+    approx::assert_relative_eq!(Activation::Tanh.apply(-1.0f32), -0.7615942);
+    approx::assert_relative_eq!(Activation::Identity.apply(-3.5f32), -3.5);
+    approx::assert_relative_eq!(Activation::Softsign.apply(-3.0f32), -0.75);
+}
+
+#[test]
+fn derivatives() {
+    let activations = [
+        Activation::ReLU,
+        Activation::LeakyReLU(0.1),
+        Activation::Sigmoid,
+        Activation::Tanh,
+        Activation::Identity,
+        Activation::Softsign,
+    ];
+
+    for activation in activations {
+        for x in [-1.5f32, -0.3, 0.4, 2.0] {
+            let numeric = (activation.apply(x + 1e-3) - activation.apply(x - 1e-3)) / 2e-3;
+            let analytic = activation.derivative(x, activation.apply(x));
+
+            approx::assert_relative_eq!(analytic, numeric, epsilon = 1e-2);
+        }
+    }
+}
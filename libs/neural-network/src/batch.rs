@@ -0,0 +1,232 @@
+use crate::*;
+
+/// Many networks of the same topology, evaluated together.
+///
+/// Parameters are stored interleaved across networks - first the weight of
+/// connection #0 for every network, then connection #1 and so on - so the
+/// innermost loop of `propagate_into()` walks contiguous memory over the
+/// networks and can be autovectorized.
+///
+/// Recurrent layers keep per-network state and are not supported here,
+/// and neither are layers reading anything but the layer before them.
+#[derive(Clone, Debug)]
+pub struct NetworkBatch {
+    size: usize,
+    layers: Vec<BatchLayer>,
+    head: OutputHead,
+}
+
+#[derive(Clone, Debug)]
+struct BatchLayer {
+    input_size: usize,
+    output_size: usize,
+    /// `output_size x input_size x size`
+    weights: Vec<f32>,
+    /// `output_size x size`
+    biases: Vec<f32>,
+    activation: Activation,
+}
+
+impl NetworkBatch {
+    /// Packs `networks`, which must all share the same topology and head.
+    pub fn new<'a>(networks: impl IntoIterator<Item = &'a Network>) -> Result<Self, NetworkError> {
+        let networks: Vec<&Network> = networks.into_iter().collect();
+        let size = networks.len();
+
+        let Some(first) = networks.first() else {
+            return Ok(Self { size, layers: Vec::new(), head: OutputHead::Raw });
+        };
+
+        let topology = first.topology();
+        if networks.iter().any(|network| network.topology() != topology || network.head != first.head) {
+            return Err(NetworkError::TopologyMismatch);
+        }
+        let unsupported = |layer: &LayerTopology| layer.kind != LayerKind::Dense || !layer.sources.is_previous();
+        if let Some(layer) = topology.iter().position(unsupported) {
+            return Err(NetworkError::UnsupportedLayer(layer));
+        }
+
+        let layers = first.layers.iter().enumerate().map(|(idx, layer)| {
+            let mut weights = vec![0.0; layer.weights.len() * size];
+            let mut biases = vec![0.0; layer.biases.len() * size];
+
+            for (n, network) in networks.iter().enumerate() {
+                let source = &network.layers[idx];
+
+                for (conn, &weight) in source.weights.iter().enumerate() {
+                    weights[conn * size + n] = weight;
+                }
+                for (neuron, &bias) in source.biases.iter().enumerate() {
+                    biases[neuron * size + n] = bias;
+                }
+            }
+
+            BatchLayer {
+                input_size: layer.input_size,
+                output_size: layer.output_size(),
+                weights,
+                biases,
+                activation: layer.activation,
+            }
+        }).collect();
+
+        Ok(Self { size, layers, head: first.head.clone() })
+    }
+
+    /// Number of networks in the batch.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut scratch = Scratch::default();
+        self.propagate_into(inputs, &mut scratch).to_vec()
+    }
+
+    /// Evaluates every network at once; `inputs` holds the input vector of
+    /// network #0, followed by network #1's and so on, and the returned
+    /// outputs are laid out the same way.
+    pub fn propagate_into<'a>(&self, inputs: &[f32], scratch: &'a mut Scratch) -> &'a [f32] {
+        let (Some(first), Some(last)) = (self.layers.first(), self.layers.last()) else {
+            return &[];
+        };
+        assert_eq!(inputs.len(), first.input_size * self.size);
+
+        let width: usize = self.layers.iter().map(|layer| layer.output_size).sum();
+        let len = last.output_size * self.size;
+        scratch.reserve(inputs.len() + width * self.size, len, 0);
+
+        // like in `Network::propagate_into()`, every layer's output follows
+        // the ones before it
+        transpose(inputs, &mut scratch.values[..inputs.len()], self.size);
+        let mut offset = inputs.len();
+
+        for layer in &self.layers {
+            let (done, rest) = scratch.values.split_at_mut(offset);
+            let width = layer.output_size * self.size;
+
+            layer.propagate_into(&done[offset - layer.input_size * self.size..], &mut rest[..width], self.size);
+            offset += width;
+        }
+
+        let outputs = &mut scratch.staging[..len];
+        transpose(&scratch.values[offset - len..offset], outputs, last.output_size);
+        for output in outputs.chunks_exact_mut(last.output_size) {
+            self.head.apply(output);
+        }
+        outputs
+    }
+}
+
+impl BatchLayer {
+    fn propagate_into(&self, input: &[f32], output: &mut [f32], size: usize) {
+        let rows = self.weights.chunks_exact(self.input_size * size);
+        let biases = self.biases.chunks_exact(size);
+
+        for ((output, row), biases) in output.chunks_exact_mut(size).zip(rows).zip(biases) {
+            output.fill(0.0);
+
+            for (weights, input) in row.chunks_exact(size).zip(input.chunks_exact(size)) {
+                for ((output, &weight), &input) in output.iter_mut().zip(weights).zip(input) {
+                    *output += weight * input;
+                }
+            }
+
+            for (output, &bias) in output.iter_mut().zip(biases) {
+                *output = self.activation.apply(*output + bias);
+            }
+        }
+    }
+}
+
+/// Converts `from`, viewed as a row-major matrix of `rows x (len / rows)`,
+/// into its column-major counterpart.
+fn transpose(from: &[f32], to: &mut [f32], rows: usize) {
+    if rows == 0 {
+        return;
+    }
+
+    let cols = from.len() / rows;
+    for (row, values) in from.chunks_exact(cols).enumerate() {
+        for (col, &value) in values.iter().enumerate() {
+            to[col * rows + row] = value;
+        }
+    }
+}
+
+#[test]
+fn test_batch_matches_individual_networks() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let topology = [
+        LayerTopology { neurons: 3, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 5, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 2, activation: Activation::Tanh, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    let mut networks: Vec<_> = (0..7).map(|_| Network::random(&mut rng, &topology)).collect();
+    let inputs: Vec<f32> = (0..7 * 3).map(|_| rng.gen_range(-1.0..=1.0)).collect();
+
+    let batch = NetworkBatch::new(&networks).unwrap();
+    let outputs = batch.propagate(&inputs);
+
+    assert_eq!(batch.len(), 7);
+    for (n, network) in networks.iter_mut().enumerate() {
+        let expected = network.propagate(inputs[n * 3..][..3].to_vec());
+        assert_eq!(outputs[n * 2..][..2], expected);
+    }
+}
+
+#[test]
+fn test_empty_batch() {
+    let batch = NetworkBatch::new(&[]).unwrap();
+
+    assert!(batch.is_empty());
+    assert!(batch.propagate(&[]).is_empty());
+}
+
+#[test]
+fn test_batch_applies_head() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let builder = Network::builder().input(2).layer(3, Activation::Identity).head(OutputHead::Softmax);
+    let mut networks: Vec<_> = (0..3).map(|_| builder.clone().build_random(&mut rng).unwrap()).collect();
+
+    let outputs = NetworkBatch::new(&networks).unwrap().propagate(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]);
+
+    for (n, network) in networks.iter_mut().enumerate() {
+        let expected = network.propagate(vec![0.1 + 0.2 * n as f32, 0.2 + 0.2 * n as f32]);
+        for (actual, expected) in outputs[n * 3..][..3].iter().zip(&expected) {
+            approx::assert_relative_eq!(actual, expected);
+        }
+        approx::assert_relative_eq!(outputs[n * 3..][..3].iter().sum::<f32>(), 1.0);
+    }
+}
+
+#[test]
+fn test_batch_rejects_mismatched_networks() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let builder = Network::builder().input(2).layer(3, Activation::Identity);
+    let network = builder.clone().build_random(&mut rng).unwrap();
+
+    let wider = Network::builder().input(2).layer(4, Activation::Identity).build_random(&mut rng).unwrap();
+    assert_eq!(NetworkBatch::new([&network, &wider]).unwrap_err(), NetworkError::TopologyMismatch);
+
+    let softmax = builder.head(OutputHead::Softmax).build_random(&mut rng).unwrap();
+    assert_eq!(NetworkBatch::new([&network, &softmax]).unwrap_err(), NetworkError::TopologyMismatch);
+
+    let recurrent = Network::builder()
+        .input(2)
+        .layer_of_kind(3, Activation::Tanh, LayerKind::Elman)
+        .layer(1, Activation::Identity)
+        .build_random(&mut rng)
+        .unwrap();
+    assert_eq!(NetworkBatch::new([&recurrent]).unwrap_err(), NetworkError::UnsupportedLayer(1));
+}
@@ -0,0 +1,38 @@
+//! Renders a saved network without opening the simulation's window:
+//!
+//! ```text
+//! nn-diagram <network file> [dot|svg] > champion.svg
+//! ```
+
+use lib_neural_network::Network;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: nn-diagram <network file> [dot|svg]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let (path, format) = match args.as_slice() {
+        [path] => (path, "svg"),
+        [path, format] if format == "dot" || format == "svg" => (path, format.as_str()),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let network = match Network::load(path) {
+        Ok(network) => network,
+        Err(err) => {
+            eprintln!("couldn't load {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match format {
+        "dot" => print!("{}", network.to_dot()),
+        _ => print!("{}", network.to_svg()),
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,175 @@
+use crate::*;
+
+/// Step-by-step construction of a network's topology:
+///
+/// ```
+/// # use lib_neural_network::*;
+/// let network = Network::builder()
+///     .input(9)
+///     .layer(18, Activation::ReLU)
+///     .layer(2, Activation::Tanh)
+///     .build_from_weights(vec![0.0; 218])
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct NetworkBuilder {
+    /// Convolutions are left unsized here, see `topology()`
+    layers: Vec<LayerTopology>,
+    /// Whether `layers` starts with an input layer yet
+    has_input: bool,
+    /// Sources of every layer as requested, kept to report the ones that
+    /// `Sources` can't hold when building
+    sources: Vec<Vec<usize>>,
+    initializer: Initializer,
+    head: OutputHead,
+}
+
+impl NetworkBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the size of the input layer, replacing any set before.
+    pub fn input(mut self, neurons: usize) -> Self {
+        let input = LayerTopology {
+            neurons,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+            sources: Sources::default(),
+        };
+
+        if self.has_input {
+            self.layers[0] = input;
+        } else {
+            self.layers.insert(0, input);
+            self.sources.insert(0, Vec::new());
+            self.has_input = true;
+        }
+        self
+    }
+
+    /// Appends a dense layer.
+    pub fn layer(self, neurons: usize, activation: Activation) -> Self {
+        self.layer_of_kind(neurons, activation, LayerKind::Dense)
+    }
+
+    pub fn layer_of_kind(mut self, neurons: usize, activation: Activation, kind: LayerKind) -> Self {
+        self.layers.push(LayerTopology { neurons, activation, kind, sources: Sources::default() });
+        self.sources.push(Vec::new());
+        self
+    }
+
+    /// Appends a convolution, sized to fit whatever it ends up reading.
+    pub fn conv1d(self, conv: Conv1d, activation: Activation) -> Self {
+        self.layer_of_kind(0, activation, LayerKind::Conv1d(conv))
+    }
+
+    /// Makes the layer added last read `layers` - counting the input layer
+    /// as #0 - instead of just the one before it, e.g. `[0, 1]` to have it
+    /// see the raw inputs next to the first hidden layer.
+    pub fn sources(mut self, layers: impl IntoIterator<Item = usize>) -> Self {
+        let (Some(layer), Some(sources)) = (self.layers.last_mut(), self.sources.last_mut()) else {
+            return self;
+        };
+
+        *sources = layers.into_iter().collect();
+        if sources.iter().all(|&source| source < Sources::LIMIT) {
+            layer.sources = Sources::new(sources.iter().copied());
+        }
+        self
+    }
+
+    /// Picks how `build_random()` draws the starting weights.
+    pub fn initializer(mut self, initializer: Initializer) -> Self {
+        self.initializer = initializer;
+        self
+    }
+
+    /// Picks how the network's output gets post-processed.
+    pub fn head(mut self, head: OutputHead) -> Self {
+        self.head = head;
+        self
+    }
+
+    /// Topology as built so far, with every convolution sized to fit its
+    /// input - or, when it can't be applied to it, left with no neurons.
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        let mut layers = self.layers.clone();
+
+        for idx in 0..layers.len() {
+            if let LayerKind::Conv1d(conv) = layers[idx].kind {
+                layers[idx].neurons = conv.output_size(Network::input_size(&layers, idx)).unwrap_or(0);
+            }
+        }
+        layers
+    }
+
+    pub fn build_random(self, rng: &mut dyn rand::RngCore) -> Result<Network, NetworkError> {
+        self.validate_sources()?;
+        let layers = self.topology();
+        Network::validate(&layers)?;
+
+        let mut network = Network::random_with(rng, &layers, self.initializer);
+        network.set_head(self.head)?;
+        Ok(network)
+    }
+
+    pub fn build_from_weights(self, weights: impl IntoIterator<Item = f32>) -> Result<Network, NetworkError> {
+        self.validate_sources()?;
+        let mut network = Network::try_from_weights(&self.topology(), weights)?;
+        network.set_head(self.head)?;
+        Ok(network)
+    }
+
+    /// Reports layers given sources beyond what `Sources` can hold, which
+    /// couldn't make it into the topology.
+    fn validate_sources(&self) -> Result<(), NetworkError> {
+        match self.sources.iter().position(|sources| sources.iter().any(|&source| source >= Sources::LIMIT)) {
+            Some(layer) => Err(NetworkError::BadSources(layer)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_builder() {
+    use rand::SeedableRng;
+
+    let builder = Network::builder()
+        .layer(4, Activation::ReLU)
+        .layer_of_kind(2, Activation::Tanh, LayerKind::Elman)
+        .input(3);
+    assert_eq!(builder.topology()[0].neurons, 3);
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let network = builder.clone().build_random(&mut rng).unwrap();
+    assert_eq!(network.topology(), builder.topology());
+    assert_eq!(network.weights().len(), 4 * 4 + 2 * 7);
+
+    assert_eq!(
+        Network::builder().input(3).build_random(&mut rng).unwrap_err(),
+        NetworkError::EmptyTopology
+    );
+    assert_eq!(
+        Network::builder().input(3).layer(0, Activation::ReLU).build_from_weights([]).unwrap_err(),
+        NetworkError::EmptyLayer(1)
+    );
+
+    // sources beyond `Sources::LIMIT` are reported rather than panicking
+    let builder = Network::builder().layer(1, Activation::ReLU).sources([0, 64]).input(2);
+    assert_eq!(builder.clone().build_from_weights([]).unwrap_err(), NetworkError::BadSources(1));
+    assert_eq!(builder.build_random(&mut rng).unwrap_err(), NetworkError::BadSources(1));
+
+    // setting the input again replaces it
+    let builder = Network::builder().input(3).layer(1, Activation::ReLU).input(4);
+    assert_eq!(builder.topology().len(), 2);
+    assert_eq!(builder.topology()[0].neurons, 4);
+
+    // convolutions fit the input set after them, and report inputs they
+    // can't be applied to
+    let conv = Conv1d { kernel: 3, stride: 1, input_channels: 1, channels: 2, padding: Padding::Valid };
+    let builder = Network::builder().conv1d(conv, Activation::ReLU).layer(1, Activation::Tanh);
+    assert_eq!(builder.clone().input(5).topology()[1].neurons, 6);
+    assert_eq!(builder.clone().input(5).build_random(&mut rng).unwrap().topology()[1].neurons, 6);
+    assert_eq!(builder.input(2).build_random(&mut rng).unwrap_err(), NetworkError::BadConvolution(1));
+}
@@ -0,0 +1,188 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Shape of a 1D convolution layer: a handful of filters slid along the
+/// input, so that every position is processed by the same (shared) weights.
+///
+/// Both input and output are laid out position by position, with all the
+/// channels of a position next to each other - for a single input channel
+/// that's just the order `Eye` produces its cells in. A layer of this kind
+/// has `channels * positions` neurons, see `output_size()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conv1d {
+    /// Number of neighbouring positions each filter looks at
+    pub kernel: usize,
+    /// Distance between positions the filters are applied at
+    pub stride: usize,
+    /// Number of values per position of the input
+    pub input_channels: usize,
+    /// Number of filters, i.e. values per position of the output
+    pub channels: usize,
+    pub padding: Padding,
+}
+
+/// What filters see past the edges of their input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Padding {
+    /// Nothing - filters are only applied where they fit whole, so the
+    /// output is shorter than the input
+    Valid,
+    /// Zeros; filters are centered on their positions, so that (with stride
+    /// 1) the output is as long as the input
+    #[default]
+    Zero,
+    /// The other end of the input, as if it was a ring
+    Wrap,
+}
+
+impl Conv1d {
+    /// Number of values produced for an input of given size, or `None` if
+    /// the convolution can't be applied to it.
+    pub fn output_size(&self, input_size: usize) -> Option<usize> {
+        if self.kernel == 0 || self.stride == 0 || self.channels == 0 || self.input_channels == 0 {
+            return None;
+        }
+
+        if !input_size.is_multiple_of(self.input_channels) {
+            return None;
+        }
+
+        let length = input_size / self.input_channels;
+        let positions = match self.padding {
+            Padding::Valid => length.checked_sub(self.kernel)? / self.stride + 1,
+            Padding::Zero | Padding::Wrap => length.div_ceil(self.stride),
+        };
+
+        (positions > 0).then_some(positions * self.channels)
+    }
+
+    /// Number of weights of a single filter.
+    pub(crate) fn filter_size(&self) -> usize {
+        self.kernel * self.input_channels
+    }
+
+    /// Input position seen by given tap of the kernel, when applied at given
+    /// output position; `None` for zero padding.
+    pub(crate) fn source(&self, length: usize, position: usize, tap: usize) -> Option<usize> {
+        let start = position * self.stride + tap;
+
+        match self.padding {
+            Padding::Valid => Some(start),
+            Padding::Zero => start.checked_sub((self.kernel - 1) / 2).filter(|&source| source < length),
+            Padding::Wrap => {
+                let source = start as isize - ((self.kernel - 1) / 2) as isize;
+                Some(source.rem_euclid(length as isize) as usize)
+            }
+        }
+    }
+
+    /// Computes weighted sums (including biases) for every output value;
+    /// `weights` hold `filter_size()` values per filter, tap by tap.
+    pub(crate) fn sums_into<T: Scalar>(&self, weights: &[T], biases: &[T], input: &[T], sums: &mut [T]) {
+        let length = input.len() / self.input_channels;
+
+        for (position, sums) in sums.chunks_exact_mut(self.channels).enumerate() {
+            let filters = weights.chunks_exact(self.filter_size());
+
+            for ((sum, filter), &bias) in sums.iter_mut().zip(filters).zip(biases) {
+                *sum = bias;
+
+                for (tap, weights) in filter.chunks_exact(self.input_channels).enumerate() {
+                    if let Some(source) = self.source(length, position, tap) {
+                        let values = &input[source * self.input_channels..][..self.input_channels];
+                        *sum += weights.iter().zip(values).map(|(&weight, &value)| weight * value).sum::<T>();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn conv(kernel: usize, stride: usize, padding: Padding) -> Conv1d {
+    Conv1d { kernel, stride, input_channels: 1, channels: 1, padding }
+}
+
+#[test]
+fn test_output_size() {
+    assert_eq!(conv(3, 1, Padding::Zero).output_size(9), Some(9));
+    assert_eq!(conv(3, 2, Padding::Wrap).output_size(9), Some(5));
+    assert_eq!(conv(3, 1, Padding::Valid).output_size(9), Some(7));
+    assert_eq!(conv(3, 2, Padding::Valid).output_size(9), Some(4));
+    assert_eq!(conv(10, 1, Padding::Valid).output_size(9), None);
+
+    let conv = Conv1d { kernel: 3, stride: 1, input_channels: 2, channels: 4, padding: Padding::Zero };
+    assert_eq!(conv.output_size(10), Some(20));
+    assert_eq!(conv.output_size(9), None);
+}
+
+#[test]
+fn test_padding() {
+    let input = [1.0, 2.0, 3.0, 4.0];
+    let weights = [1.0, 10.0, 100.0];
+    let sums = |padding| {
+        let conv = conv(3, 1, padding);
+        let mut sums = vec![0.0; conv.output_size(input.len()).unwrap()];
+        conv.sums_into(&weights, &[0.5], &input, &mut sums);
+        sums
+    };
+
+    assert_eq!(sums(Padding::Valid), &[321.5, 432.5]);
+    assert_eq!(sums(Padding::Zero), &[210.5, 321.5, 432.5, 43.5]);
+    assert_eq!(sums(Padding::Wrap), &[214.5, 321.5, 432.5, 143.5]);
+}
+
+#[test]
+fn test_channels() {
+    // two input channels, two filters: one sums the channels, the other
+    // takes their difference, both over a single position
+    let conv = Conv1d { kernel: 1, stride: 1, input_channels: 2, channels: 2, padding: Padding::Valid };
+    let mut sums = [0.0; 4];
+    conv.sums_into(&[1.0, 1.0, 1.0, -1.0], &[0.0, 0.0], &[3.0, 1.0, 5.0, 2.0], &mut sums);
+
+    assert_eq!(sums, [4.0, 2.0, 7.0, 3.0]);
+}
+
+#[test]
+fn test_conv_network() {
+    use crate::*;
+
+    let conv = Conv1d { kernel: 3, stride: 1, input_channels: 1, channels: 2, padding: Padding::Wrap };
+    let builder = Network::builder()
+        .input(9)
+        .conv1d(conv, Activation::ReLU)
+        .layer(2, Activation::Tanh);
+    assert_eq!(builder.topology()[1].neurons, 18);
+
+    // 2 filters of 1 bias + 3 weights, instead of 18 neurons of 1 + 9
+    let genes = Network::weight_count(&builder.topology());
+    assert_eq!(genes, 2 * 4 + 2 * 19);
+
+    let weights: Vec<f32> = (0..genes).map(|gene| (gene as f32 * 0.37).sin()).collect();
+    let network = builder.build_from_weights(weights.clone()).unwrap();
+    assert_eq!(network.weights(), weights);
+    assert_eq!(network.topology()[1].kind, LayerKind::Conv1d(conv));
+
+    // shifting the input around the ring shifts the convolution's output
+    // the same way, so the first layer's activations must be a permutation
+    let shifted = |shift: usize| {
+        let mut inputs = [0.0; 9];
+        inputs[shift] = 1.0;
+        let mut activations = network.propagate_traced(inputs.to_vec()).layers[0].activations.clone();
+        activations.sort_by(f32::total_cmp);
+        activations
+    };
+    assert_eq!(shifted(0), shifted(5));
+
+    let bytes = network.to_bytes(FileFormat::Json).unwrap();
+    assert_eq!(Network::from_bytes(&bytes).unwrap().weights(), weights);
+
+    assert_eq!(
+        Network::builder()
+            .input(2)
+            .conv1d(Conv1d { padding: Padding::Valid, ..conv }, Activation::ReLU)
+            .build_from_weights([])
+            .unwrap_err(),
+        NetworkError::BadConvolution(1)
+    );
+}
@@ -0,0 +1,280 @@
+//! Continuous-time recurrent neural networks: every neuron has a membrane
+//! potential `y` which, rather than being recomputed from scratch on every
+//! call, drifts towards its inputs at a pace set by the neuron's time
+//! constant `tau`:
+//!
+//! ```text
+//! tau_i * dy_i/dt = -y_i + sum_j w_ij * f(y_j + bias_j) + sum_k v_ik * input_k
+//! ```
+//!
+//! Time is advanced explicitly, through `Ctrnn::step()`.
+
+use crate::*;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Range random time constants are drawn from.
+const TIME_CONSTANTS: RangeInclusive<f32> = 0.5..=5.0;
+
+/// Shape of a CTRNN; every neuron is connected to every input and to every
+/// neuron (itself included).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CtrnnTopology {
+    pub inputs: usize,
+    pub neurons: usize,
+    /// Number of neurons, counting from the last one, whose firing rates
+    /// make up the network's output
+    pub outputs: usize,
+    /// Turns membrane potentials into firing rates
+    pub activation: Activation,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ctrnn {
+    topology: CtrnnTopology,
+    time_constants: Vec<f32>,
+    biases: Vec<f32>,
+    /// Row-major `neurons x inputs`
+    input_weights: Vec<f32>,
+    /// Row-major `neurons x neurons`; row `i` holds connections into neuron `i`
+    weights: Vec<f32>,
+    /// Membrane potentials
+    state: Vec<f32>,
+    inputs: Vec<f32>,
+    /// Firing rates, kept around so that `step()` doesn't allocate
+    rates: Vec<f32>,
+}
+
+impl Ctrnn {
+    pub fn random(rng: &mut dyn RngCore, topology: CtrnnTopology) -> Self {
+        Self::validate(&topology).unwrap_or_else(|err| panic!("{err}"));
+
+        let CtrnnTopology { inputs, neurons, .. } = topology;
+        let initializer = Initializer::default();
+
+        let weights = (0..neurons).flat_map(|_| {
+            let time_constant = rng.gen_range(TIME_CONSTANTS);
+            let bias = initializer.bias(rng);
+            let weights: Vec<f32> = (0..inputs + neurons)
+                .map(|_| initializer.weight(rng, inputs + neurons, neurons))
+                .collect();
+
+            [time_constant, bias].into_iter().chain(weights)
+        });
+        let weights: Vec<f32> = weights.collect();
+
+        Self::from_weights(topology, weights).expect("generated the wrong number of genes")
+    }
+
+    /// Rebuilds a network out of genes laid out as `weights()` returns them.
+    pub fn from_weights(topology: CtrnnTopology, weights: impl IntoIterator<Item = f32>) -> Result<Self, NetworkError> {
+        Self::validate(&topology)?;
+
+        let weights: Vec<f32> = weights.into_iter().collect();
+        let expected = Self::weight_count(&topology);
+        if weights.len() != expected {
+            return Err(NetworkError::WrongGeneCount { expected, actual: weights.len() });
+        }
+
+        let CtrnnTopology { inputs, neurons, .. } = topology;
+        let mut network = Self {
+            topology,
+            time_constants: Vec::with_capacity(neurons),
+            biases: Vec::with_capacity(neurons),
+            input_weights: Vec::with_capacity(neurons * inputs),
+            weights: Vec::with_capacity(neurons * neurons),
+            state: vec![0.0; neurons],
+            inputs: vec![0.0; inputs],
+            rates: vec![0.0; neurons],
+        };
+
+        for genes in weights.chunks_exact(2 + inputs + neurons) {
+            network.time_constants.push(genes[0]);
+            network.biases.push(genes[1]);
+            network.input_weights.extend_from_slice(&genes[2..][..inputs]);
+            network.weights.extend_from_slice(&genes[2 + inputs..]);
+        }
+
+        Ok(network)
+    }
+
+    /// Number of genes `from_weights()` expects for given topology.
+    pub fn weight_count(topology: &CtrnnTopology) -> usize {
+        topology.neurons * (2 + topology.inputs + topology.neurons)
+    }
+
+    /// Genes are laid out neuron by neuron: the time constant, the bias,
+    /// the weights of inputs and then the weights of other neurons.
+    pub fn weights(&self) -> Vec<f32> {
+        let CtrnnTopology { inputs, neurons, .. } = self.topology;
+
+        (0..neurons)
+            .flat_map(|neuron| {
+                [self.time_constants[neuron], self.biases[neuron]]
+                    .into_iter()
+                    .chain(self.input_weights[neuron * inputs..][..inputs].iter().copied())
+                    .chain(self.weights[neuron * neurons..][..neurons].iter().copied())
+            })
+            .collect()
+    }
+
+    pub fn topology(&self) -> CtrnnTopology {
+        self.topology
+    }
+
+    /// Membrane potentials of all the neurons.
+    pub fn state(&self) -> &[f32] {
+        &self.state
+    }
+
+    pub fn reset_state(&mut self) {
+        self.state.fill(0.0);
+    }
+
+    /// Sets inputs the network will integrate over the following steps.
+    pub fn set_inputs(&mut self, inputs: &[f32]) {
+        self.inputs.copy_from_slice(inputs);
+    }
+
+    /// Advances the network by `dt`, using the forward Euler method.
+    ///
+    /// Time constants shorter than `dt` are treated as `dt` - otherwise the
+    /// integration would overshoot and could blow up - which lets evolution
+    /// mutate them freely.
+    ///
+    /// Panics if `dt` is not positive, since a time constant of zero would
+    /// turn the potentials into NaNs.
+    pub fn step(&mut self, dt: f32) {
+        assert!(dt > 0.0, "got a non-positive time step: {dt}");
+
+        let CtrnnTopology { inputs, neurons, activation, .. } = self.topology;
+
+        for ((rate, &state), &bias) in self.rates.iter_mut().zip(&self.state).zip(&self.biases) {
+            *rate = activation.apply(state + bias);
+        }
+
+        for neuron in 0..neurons {
+            let input_row = &self.input_weights[neuron * inputs..][..inputs];
+            let row = &self.weights[neuron * neurons..][..neurons];
+
+            let input: f32 = input_row.iter().zip(&self.inputs).map(|(&weight, &input)| weight * input).sum();
+            let recurrent: f32 = row.iter().zip(&self.rates).map(|(&weight, &rate)| weight * rate).sum();
+
+            let time_constant = self.time_constants[neuron].max(dt);
+            let state = &mut self.state[neuron];
+            *state += dt / time_constant * (input + recurrent - *state);
+        }
+    }
+
+    /// Firing rates of the output neurons.
+    pub fn outputs(&self) -> Vec<f32> {
+        let first = self.topology.neurons - self.topology.outputs;
+
+        self.state[first..]
+            .iter()
+            .zip(&self.biases[first..])
+            .map(|(&state, &bias)| self.topology.activation.apply(state + bias))
+            .collect()
+    }
+
+    /// Feeds `inputs`, makes a single step and returns the outputs.
+    pub fn propagate(&mut self, inputs: &[f32], dt: f32) -> Vec<f32> {
+        self.set_inputs(inputs);
+        self.step(dt);
+        self.outputs()
+    }
+
+    /// Counting inputs as layer #0, neurons as #1 and outputs as #2.
+    fn validate(topology: &CtrnnTopology) -> Result<(), NetworkError> {
+        let sizes = [topology.inputs, topology.neurons, topology.outputs];
+        if let Some(layer) = sizes.iter().position(|&size| size == 0) {
+            return Err(NetworkError::EmptyLayer(layer));
+        }
+
+        if topology.outputs > topology.neurons {
+            return Err(NetworkError::ShapeMismatch { expected: topology.neurons, actual: topology.outputs });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn single_neuron(time_constant: f32) -> Ctrnn {
+    let topology = CtrnnTopology { inputs: 1, neurons: 1, outputs: 1, activation: Activation::Identity };
+
+    // input weight 1, no self-connection
+    Ctrnn::from_weights(topology, [time_constant, 0.0, 1.0, 0.0]).unwrap()
+}
+
+#[test]
+fn test_genes() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let topology = CtrnnTopology { inputs: 3, neurons: 4, outputs: 2, activation: Activation::Tanh };
+    let network = Ctrnn::random(&mut rng, topology);
+
+    let weights = network.weights();
+    assert_eq!(weights.len(), Ctrnn::weight_count(&topology));
+    assert_eq!(weights.len(), 4 * (2 + 3 + 4));
+    assert!(TIME_CONSTANTS.contains(&weights[0]));
+
+    let rebuilt = Ctrnn::from_weights(topology, weights.clone()).unwrap();
+    assert_eq!(rebuilt.weights(), weights);
+
+    assert_eq!(
+        Ctrnn::from_weights(topology, [0.0; 3]).unwrap_err(),
+        NetworkError::WrongGeneCount { expected: 36, actual: 3 }
+    );
+    assert_eq!(
+        Ctrnn::from_weights(CtrnnTopology { outputs: 5, ..topology }, []).unwrap_err(),
+        NetworkError::ShapeMismatch { expected: 4, actual: 5 }
+    );
+}
+
+#[test]
+fn test_integrates_over_time() {
+    let mut network = single_neuron(1.0);
+
+    network.set_inputs(&[1.0]);
+    for _ in 0..100 {
+        network.step(0.01);
+    }
+
+    // y(t) = 1 - e^(-t / tau)
+    approx::assert_relative_eq!(network.outputs()[0], 1.0 - (-1.0f32).exp(), epsilon = 0.01);
+
+    network.reset_state();
+    assert_eq!(network.state(), &[0.0]);
+}
+
+#[test]
+fn test_time_constants() {
+    let mut fast = single_neuron(0.5);
+    let mut slow = single_neuron(5.0);
+
+    let fast = fast.propagate(&[1.0], 0.1)[0];
+    let slow = slow.propagate(&[1.0], 0.1)[0];
+    approx::assert_relative_eq!(fast, 0.2);
+    approx::assert_relative_eq!(slow, 0.02);
+
+    // shorter than the step itself, so reaches the input right away
+    let mut instant = single_neuron(-3.0);
+    assert_eq!(instant.propagate(&[1.0], 0.1), &[1.0]);
+    assert_eq!(instant.propagate(&[1.0], 0.1), &[1.0]);
+}
+
+#[test]
+fn test_recurrence() {
+    let topology = CtrnnTopology { inputs: 1, neurons: 2, outputs: 1, activation: Activation::Identity };
+    // neuron #0 reads the input, neuron #1 only reads neuron #0
+    let mut network = Ctrnn::from_weights(topology, [
+        0.0, 0.0, 1.0, 0.0, 0.0,
+        0.0, 0.5, 0.0, 2.0, 0.0,
+    ]).unwrap();
+
+    // the output neuron lags a step behind, seeing neuron #0's rate from
+    // before that step
+    assert_eq!(network.propagate(&[3.0], 1.0), &[0.5]);
+    assert_eq!(network.propagate(&[3.0], 1.0), &[6.5]);
+}
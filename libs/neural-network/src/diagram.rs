@@ -0,0 +1,352 @@
+//! Renders networks as diagrams - Graphviz DOT, for further tweaking, and
+//! self-contained SVG, for a quick look at an evolved network's wiring.
+//!
+//! In both, edges are green for positive and red for negative weights, and
+//! the stronger a weight, the thicker its edge; every neuron is annotated
+//! with its bias. Context weights of recurrent layers are drawn dashed.
+
+use crate::*;
+use std::fmt::Write;
+
+const POSITIVE: &str = "#2e7d32";
+const NEGATIVE: &str = "#c62828";
+const MIN_STROKE: f32 = 0.25;
+const MAX_STROKE: f32 = 4.0;
+
+const MARGIN: f32 = 40.0;
+const LAYER_GAP: f32 = 160.0;
+const NEURON_GAP: f32 = 48.0;
+const NEURON_RADIUS: f32 = 16.0;
+
+/// Single weighted connection; neurons are identified by `(layer, neuron)`,
+/// where layer 0 is the input layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Edge {
+    from: (usize, usize),
+    to: (usize, usize),
+    weight: f32,
+    /// Carries a value remembered from the previous call
+    recurrent: bool,
+}
+
+impl Network {
+    pub fn to_dot(&self) -> String {
+        let edges = self.edges();
+        let strongest = strongest(&edges);
+        let mut dot = String::new();
+
+        // (writing into a `String` never fails)
+        writeln!(dot, "digraph network {{").unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+        writeln!(dot, "    splines=line;").unwrap();
+        writeln!(dot, "    node [shape=circle, fixedsize=true, width=0.6, fontsize=10];").unwrap();
+
+        for (layer, label) in self.layer_labels().iter().enumerate() {
+            writeln!(dot).unwrap();
+            writeln!(dot, "    subgraph cluster_{layer} {{").unwrap();
+            writeln!(dot, "        label=\"{label}\";").unwrap();
+            writeln!(dot, "        color=lightgrey;").unwrap();
+
+            for (neuron, label) in self.neuron_labels(layer).iter().enumerate() {
+                writeln!(dot, "        n{layer}_{neuron} [label=\"{label}\"];").unwrap();
+            }
+
+            writeln!(dot, "    }}").unwrap();
+        }
+
+        writeln!(dot).unwrap();
+        for edge in &edges {
+            let (from_layer, from_neuron) = edge.from;
+            let (to_layer, to_neuron) = edge.to;
+
+            write!(
+                dot,
+                "    n{from_layer}_{from_neuron} -> n{to_layer}_{to_neuron} [color=\"{}\", penwidth={:.2}, tooltip=\"{}\"",
+                color(edge.weight),
+                stroke(edge.weight, strongest),
+                edge.weight,
+            )
+            .unwrap();
+
+            if edge.recurrent {
+                write!(dot, ", style=dashed, constraint=false").unwrap();
+            }
+
+            writeln!(dot, "];").unwrap();
+        }
+
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    pub fn to_svg(&self) -> String {
+        let edges = self.edges();
+        let strongest = strongest(&edges);
+        let widths: Vec<usize> = self.topology().iter().map(|layer| layer.neurons).collect();
+        let tallest = widths.iter().copied().max().unwrap_or(1);
+
+        let width = 2.0 * MARGIN + LAYER_GAP * (widths.len() - 1) as f32;
+        let height = 3.0 * MARGIN + NEURON_GAP * (tallest - 1) as f32;
+
+        // Layers are centered vertically, below a row of their labels
+        let position = |(layer, neuron): (usize, usize)| {
+            let offset = (tallest - widths[layer]) as f32 / 2.0;
+            (
+                MARGIN + LAYER_GAP * layer as f32,
+                2.0 * MARGIN + NEURON_GAP * (neuron as f32 + offset),
+            )
+        };
+
+        let mut svg = String::new();
+
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" text-anchor="middle">"#,
+        )
+        .unwrap();
+        writeln!(svg, r#"  <rect width="100%" height="100%" fill="white"/>"#).unwrap();
+
+        for edge in &edges {
+            let (x1, y1) = position(edge.from);
+            let (x2, y2) = position(edge.to);
+            let style = format!(
+                r#"stroke="{}" stroke-width="{:.2}""#,
+                color(edge.weight),
+                stroke(edge.weight, strongest),
+            );
+
+            if edge.recurrent {
+                // Loops around the right side, so it's not confused with
+                // the forward edges
+                let bend = NEURON_GAP;
+                writeln!(
+                    svg,
+                    r#"  <path d="M {x1} {y1} C {} {} {} {} {x2} {y2}" fill="none" stroke-dasharray="4 3" {style}><title>{}</title></path>"#,
+                    x1 + bend,
+                    y1 - bend,
+                    x2 + bend,
+                    y2 + bend,
+                    edge.weight,
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    svg,
+                    r#"  <line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" {style}><title>{}</title></line>"#,
+                    edge.weight,
+                )
+                .unwrap();
+            }
+        }
+
+        for (layer, label) in self.layer_labels().iter().enumerate() {
+            let (x, _) = position((layer, 0));
+            writeln!(svg, r#"  <text x="{x}" y="{MARGIN}" font-size="12">{label}</text>"#).unwrap();
+
+            for (neuron, label) in self.neuron_labels(layer).iter().enumerate() {
+                let (x, y) = position((layer, neuron));
+
+                writeln!(
+                    svg,
+                    r##"  <circle cx="{x}" cy="{y}" r="{NEURON_RADIUS}" fill="#eeeeee" stroke="#616161"/>"##,
+                )
+                .unwrap();
+                writeln!(svg, r#"  <text x="{x}" y="{}" font-size="9">{label}</text>"#, y + 3.0).unwrap();
+            }
+        }
+
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+
+    fn edges(&self) -> Vec<Edge> {
+        let last = self.layers.len();
+        let mut edges = Vec::new();
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let to_layer = idx + 1;
+
+            edges.extend(layer.connections().into_iter().map(|(input, neuron, weight)| Edge {
+                from: self.source_of(idx, input),
+                to: (to_layer, neuron),
+                weight,
+                recurrent: false,
+            }));
+
+            for neuron in 0..layer.output_size() {
+                let context_size = layer.context_size;
+                if context_size == 0 {
+                    continue;
+                }
+
+                let from_layer = match layer.kind {
+                    LayerKind::Dense | LayerKind::Conv1d(_) => unreachable!(),
+                    LayerKind::Elman => to_layer,
+                    LayerKind::Jordan => last,
+                };
+                let row = &layer.context_weights[neuron * context_size..][..context_size];
+                edges.extend(row.iter().enumerate().map(|(source, &weight)| Edge {
+                    from: (from_layer, source),
+                    to: (to_layer, neuron),
+                    weight,
+                    recurrent: true,
+                }));
+            }
+        }
+
+        edges
+    }
+
+    fn layer_labels(&self) -> Vec<String> {
+        let input = format!("input ({})", self.layers[0].input_size);
+        let rest = self.layers.iter().enumerate().map(|(idx, layer)| {
+            let kind = match layer.kind {
+                LayerKind::Dense => String::new(),
+                LayerKind::Elman => " Elman".into(),
+                LayerKind::Jordan => " Jordan".into(),
+                LayerKind::Conv1d(conv) => format!(" Conv1d {}x{}/{}", conv.channels, conv.kernel, conv.stride),
+            };
+
+            let sources = if layer.sources.is_previous() {
+                String::new()
+            } else {
+                format!(", from {}", layer.sources)
+            };
+
+            format!("#{} {:?}{kind} ({}){sources}", idx + 1, layer.activation, layer.output_size())
+        });
+
+        std::iter::once(input).chain(rest).collect()
+    }
+
+    /// Layer and neuron behind the `input`-th value `self.layers[idx]` reads.
+    fn source_of(&self, idx: usize, mut input: usize) -> (usize, usize) {
+        for source in self.sources(idx) {
+            let width = self.width(source);
+            if input < width {
+                return (source, input);
+            }
+            input -= width;
+        }
+
+        unreachable!("layer #{} has no input #{input}", idx + 1)
+    }
+
+    /// Input neurons get their index, the others their bias.
+    fn neuron_labels(&self, layer: usize) -> Vec<String> {
+        if layer == 0 {
+            (0..self.layers[0].input_size).map(|input| format!("x{input}")).collect()
+        } else {
+            let layer = &self.layers[layer - 1];
+            (0..layer.output_size())
+                .map(|neuron| format!("{:+.2}", layer.biases[neuron % layer.biases.len()]))
+                .collect()
+        }
+    }
+}
+
+impl Layer {
+    /// Every forward connection as `(input, neuron, weight)`; a convolution
+    /// repeats its filters' weights at every position.
+    fn connections(&self) -> Vec<(usize, usize, f32)> {
+        let LayerKind::Conv1d(conv) = self.kind else {
+            return (0..self.output_size())
+                .flat_map(|neuron| (0..self.input_size).map(move |input| (neuron, input)))
+                .map(|(neuron, input)| (input, neuron, self.weights[neuron * self.input_size + input]))
+                .collect();
+        };
+
+        let length = self.input_size / conv.input_channels;
+        let mut connections = Vec::new();
+
+        for neuron in 0..self.output_size() {
+            let (position, filter) = (neuron / conv.channels, neuron % conv.channels);
+
+            for tap in 0..conv.kernel {
+                let Some(source) = conv.source(length, position, tap) else {
+                    continue;
+                };
+
+                for channel in 0..conv.input_channels {
+                    let weight = self.weights[filter * conv.filter_size() + tap * conv.input_channels + channel];
+                    connections.push((source * conv.input_channels + channel, neuron, weight));
+                }
+            }
+        }
+
+        connections
+    }
+}
+
+fn strongest(edges: &[Edge]) -> f32 {
+    edges.iter().map(|edge| edge.weight.abs()).fold(0.0, f32::max)
+}
+
+fn color(weight: f32) -> &'static str {
+    if weight < 0.0 {
+        NEGATIVE
+    } else {
+        POSITIVE
+    }
+}
+
+fn stroke(weight: f32, strongest: f32) -> f32 {
+    if strongest == 0.0 {
+        return MIN_STROKE;
+    }
+
+    MIN_STROKE + (MAX_STROKE - MIN_STROKE) * weight.abs() / strongest
+}
+
+#[cfg(test)]
+fn diagram_network() -> Network {
+    Network::builder()
+        .input(2)
+        .layer_of_kind(1, Activation::Tanh, LayerKind::Elman)
+        .build_from_weights([0.5, -2.0, 1.0, 0.25])
+        .unwrap()
+}
+
+#[test]
+fn test_dot() {
+    let dot = diagram_network().to_dot();
+
+    assert!(dot.starts_with("digraph network {"));
+    assert!(dot.contains(r#"label="input (2)";"#));
+    assert!(dot.contains(r##"label="#1 Tanh Elman (1)";"##));
+    assert!(dot.contains(r#"n0_0 [label="x0"];"#));
+    assert!(dot.contains(r#"n1_0 [label="+0.50"];"#));
+    assert!(dot.contains(r##"n0_0 -> n1_0 [color="#c62828", penwidth=4.00, tooltip="-2"];"##));
+    assert!(dot.contains(r##"n0_1 -> n1_0 [color="#2e7d32", penwidth=2.12, tooltip="1"];"##));
+    assert!(dot.contains(r##"n1_0 -> n1_0 [color="#2e7d32", penwidth=0.72, tooltip="0.25", style=dashed, constraint=false];"##));
+    assert!(dot.trim_end().ends_with('}'));
+}
+
+#[test]
+fn test_svg() {
+    let svg = diagram_network().to_svg();
+
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert_eq!(svg.matches("<circle ").count(), 3);
+    assert_eq!(svg.matches("<line ").count(), 2);
+    assert_eq!(svg.matches("<path ").count(), 1);
+    assert!(svg.contains(">+0.50</text>"));
+    assert!(svg.contains(r##"stroke="#c62828" stroke-width="4.00""##));
+}
+
+#[test]
+fn test_skip_connections() {
+    let dot = Network::builder()
+        .input(1)
+        .layer(1, Activation::ReLU)
+        .layer(1, Activation::Identity)
+        .sources([0, 1])
+        .build_from_weights([0.0, 1.0, 0.0, 2.0, 3.0])
+        .unwrap()
+        .to_dot();
+
+    assert!(dot.contains(r##"label="#2 Identity (1), from #0, #1";"##));
+    assert!(dot.contains(r##"n0_0 -> n2_0 [color="#2e7d32", penwidth=2.75, tooltip="2"];"##));
+    assert!(dot.contains(r##"n1_0 -> n2_0 [color="#2e7d32", penwidth=4.00, tooltip="3"];"##));
+}
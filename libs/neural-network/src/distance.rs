@@ -0,0 +1,254 @@
+//! Comparing networks of the same topology, e.g. to measure a population's
+//! diversity or to see what changed between a parent and its child.
+
+use crate::*;
+use std::fmt;
+
+/// Single parameter of a network; layers are counted from 1, like in
+/// `NetworkError`, so that layer #1 is the first one after the inputs.
+///
+/// For convolution layers, `neuron` is the filter and `input` the position
+/// within it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parameter {
+    Bias { layer: usize, neuron: usize },
+    Weight { layer: usize, neuron: usize, input: usize },
+    /// Weight of a value remembered by a recurrent layer
+    Context { layer: usize, neuron: usize, source: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParameterChange {
+    pub parameter: Parameter,
+    pub from: f32,
+    pub to: f32,
+}
+
+/// Parameters that differ between two networks, most changed first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NetworkDiff {
+    pub changes: Vec<ParameterChange>,
+    /// Number of parameters that changed at all, including those beyond
+    /// the requested limit
+    pub changed: usize,
+    pub l2_distance: f32,
+}
+
+impl Network {
+    /// Euclidean distance between the networks' genes.
+    pub fn l2_distance(&self, other: &Network) -> Result<f32, NetworkError> {
+        let (a, b) = self.comparable_weights(other)?;
+        let sum: f32 = a.iter().zip(&b).map(|(a, b)| (a - b).powi(2)).sum();
+
+        Ok(sum.sqrt())
+    }
+
+    /// `1 - cos(angle)` between the networks' genes: 0 when one is a
+    /// positively scaled copy of the other, 2 when they point the opposite
+    /// ways.
+    pub fn cosine_distance(&self, other: &Network) -> Result<f32, NetworkError> {
+        let (a, b) = self.comparable_weights(other)?;
+        let dot: f32 = a.iter().zip(&b).map(|(a, b)| a * b).sum();
+        let norm = |genes: &[f32]| genes.iter().map(|gene| gene * gene).sum::<f32>().sqrt();
+
+        let norms = norm(&a) * norm(&b);
+        if norms == 0.0 {
+            // a zero vector has no direction, so it's only similar to itself
+            return Ok(if a == b { 0.0 } else { 1.0 });
+        }
+
+        Ok(1.0 - (dot / norms).clamp(-1.0, 1.0))
+    }
+
+    /// Mean Euclidean distance between the networks' responses to `probes`.
+    ///
+    /// Recurrent layers of both networks start from a blank context, which
+    /// they carry from one probe to the next.
+    pub fn behavioural_distance<'a>(&self, other: &Network, probes: impl IntoIterator<Item = &'a [f32]>) -> Result<f32, NetworkError> {
+        self.comparable_weights(other)?;
+
+        let (mut scratch_a, mut scratch_b) = (Scratch::new(self), Scratch::new(other));
+        let expected = self.layers[0].input_size;
+        let mut total = 0.0;
+        let mut count = 0;
+
+        for probe in probes {
+            if probe.len() != expected {
+                return Err(NetworkError::ShapeMismatch { expected, actual: probe.len() });
+            }
+            let a = self.propagate_into(probe, &mut scratch_a);
+            let b = other.propagate_into(probe, &mut scratch_b);
+
+            total += a.iter().zip(b).map(|(a, b)| (a - b).powi(2)).sum::<f32>().sqrt();
+            count += 1;
+        }
+
+        Ok(if count > 0 { total / count as f32 } else { 0.0 })
+    }
+
+    /// Lists (at most `limit` of) the parameters that differ in `other`.
+    pub fn diff(&self, other: &Network, limit: usize) -> Result<NetworkDiff, NetworkError> {
+        let l2_distance = self.l2_distance(other)?;
+
+        let mut changes: Vec<ParameterChange> = self
+            .parameters()
+            .into_iter()
+            .zip(other.parameters())
+            .filter(|((_, from), (_, to))| from != to)
+            .map(|((parameter, from), (_, to))| ParameterChange { parameter, from, to })
+            .collect();
+
+        changes.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+        let changed = changes.len();
+        changes.truncate(limit);
+
+        Ok(NetworkDiff { changes, changed, l2_distance })
+    }
+
+    fn comparable_weights(&self, other: &Network) -> Result<(Vec<f32>, Vec<f32>), NetworkError> {
+        if self.topology() != other.topology() {
+            return Err(NetworkError::TopologyMismatch);
+        }
+
+        Ok((self.weights(), other.weights()))
+    }
+
+    /// Every parameter along with its value, in the order of `weights()`.
+    fn parameters(&self) -> Vec<(Parameter, f32)> {
+        let mut parameters = Vec::new();
+
+        for (idx, layer) in self.layers.iter().enumerate() {
+            let layer_no = idx + 1;
+            let rows = layer.biases.len();
+            let row_size = layer.weights.len() / rows;
+            let context_size = layer.context_size;
+
+            for neuron in 0..rows {
+                parameters.push((Parameter::Bias { layer: layer_no, neuron }, layer.biases[neuron]));
+
+                let row = &layer.weights[neuron * row_size..][..row_size];
+                parameters.extend(row.iter().enumerate().map(|(input, &weight)| {
+                    (Parameter::Weight { layer: layer_no, neuron, input }, weight)
+                }));
+
+                let row = &layer.context_weights[neuron * context_size..][..context_size];
+                parameters.extend(row.iter().enumerate().map(|(source, &weight)| {
+                    (Parameter::Context { layer: layer_no, neuron, source }, weight)
+                }));
+            }
+        }
+
+        parameters
+    }
+}
+
+impl ParameterChange {
+    pub fn delta(&self) -> f32 {
+        self.to - self.from
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bias { layer, neuron } => write!(f, "layer #{layer}, neuron {neuron}: bias"),
+            Self::Weight { layer, neuron, input } => write!(f, "layer #{layer}, neuron {neuron}: input {input}"),
+            Self::Context { layer, neuron, source } => write!(f, "layer #{layer}, neuron {neuron}: context {source}"),
+        }
+    }
+}
+
+impl fmt::Display for NetworkDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} parameters changed, L2 distance {:.4}", self.changed, self.l2_distance)?;
+
+        for change in &self.changes {
+            writeln!(f, "  {}: {:+.4} -> {:+.4} ({:+.4})", change.parameter, change.from, change.to, change.delta())?;
+        }
+
+        if self.changed > self.changes.len() {
+            writeln!(f, "  ... and {} more", self.changed - self.changes.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn distance_network(weights: [f32; 5]) -> Network {
+    Network::builder()
+        .input(2)
+        .layer(1, Activation::Identity)
+        .layer(1, Activation::Identity)
+        .build_from_weights(weights)
+        .unwrap()
+}
+
+#[test]
+fn test_weight_distances() {
+    let a = distance_network([1.0, 0.0, 0.0, 0.0, 0.0]);
+    let b = distance_network([0.0, 1.0, 0.0, 0.0, 0.0]);
+    let c = distance_network([-2.0, 0.0, 0.0, 0.0, 0.0]);
+
+    approx::assert_relative_eq!(a.l2_distance(&b).unwrap(), 2.0f32.sqrt());
+    approx::assert_relative_eq!(a.l2_distance(&c).unwrap(), 3.0);
+    assert_eq!(a.l2_distance(&a).unwrap(), 0.0);
+
+    approx::assert_relative_eq!(a.cosine_distance(&b).unwrap(), 1.0);
+    approx::assert_relative_eq!(a.cosine_distance(&c).unwrap(), 2.0);
+    assert_eq!(c.cosine_distance(&a).unwrap(), a.cosine_distance(&c).unwrap());
+    assert_eq!(a.cosine_distance(&distance_network([5.0, 0.0, 0.0, 0.0, 0.0])).unwrap(), 0.0);
+
+    let zeros = distance_network([0.0; 5]);
+    assert_eq!(zeros.cosine_distance(&zeros).unwrap(), 0.0);
+    assert_eq!(zeros.cosine_distance(&a).unwrap(), 1.0);
+
+    let other = Network::builder().input(2).layer(1, Activation::Identity).build_from_weights([0.0; 3]).unwrap();
+    assert_eq!(a.l2_distance(&other), Err(NetworkError::TopologyMismatch));
+}
+
+#[test]
+fn test_behavioural_distance() {
+    // both compute x + y, through weights of different scale
+    let a = distance_network([0.0, 1.0, 1.0, 0.0, 1.0]);
+    let b = distance_network([0.0, 2.0, 2.0, 0.0, 0.5]);
+    // ... while this one computes x + y + 1
+    let c = distance_network([0.0, 1.0, 1.0, 1.0, 1.0]);
+
+    let probes = [[0.0, 1.0], [2.0, 3.0]];
+    let probes = || probes.iter().map(|probe| probe.as_slice());
+
+    assert_eq!(a.behavioural_distance(&b, probes()).unwrap(), 0.0);
+    assert_eq!(a.behavioural_distance(&c, probes()).unwrap(), 1.0);
+    assert!(a.l2_distance(&b).unwrap() > a.l2_distance(&c).unwrap());
+
+    assert_eq!(
+        a.behavioural_distance(&b, [[1.0].as_slice()]),
+        Err(NetworkError::ShapeMismatch { expected: 2, actual: 1 })
+    );
+}
+
+#[test]
+fn test_diff() {
+    let a = distance_network([0.0, 1.0, 1.0, 0.0, 1.0]);
+    let b = distance_network([0.5, 1.0, -1.0, 0.0, 1.25]);
+
+    let diff = a.diff(&b, 2).unwrap();
+    assert_eq!(diff.changed, 3);
+    assert_eq!(
+        diff.changes,
+        &[
+            ParameterChange { parameter: Parameter::Weight { layer: 1, neuron: 0, input: 1 }, from: 1.0, to: -1.0 },
+            ParameterChange { parameter: Parameter::Bias { layer: 1, neuron: 0 }, from: 0.0, to: 0.5 },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "3 parameters changed, L2 distance 2.0767\n\
+         \x20 layer #1, neuron 0: input 1: +1.0000 -> -1.0000 (-2.0000)\n\
+         \x20 layer #1, neuron 0: bias: +0.0000 -> +0.5000 (+0.5000)\n\
+         \x20 ... and 1 more\n"
+    );
+
+    assert_eq!(a.diff(&a, 10).unwrap().changes, &[]);
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    /// Topology needs at least an input and an output layer
+    EmptyTopology,
+    /// Layer at given index (counting the input layer) has no neurons
+    EmptyLayer(usize),
+    /// Input (or target) vector does not match the size of its layer
+    ShapeMismatch { expected: usize, actual: usize },
+    /// Number of genes does not match what the topology requires
+    WrongGeneCount { expected: usize, actual: usize },
+    /// Layer at given index (counting the input layer) is of a kind the
+    /// requested operation can't handle
+    UnsupportedLayer(usize),
+    /// Convolution layer at given index (counting the input layer) can't be
+    /// applied to the layer before it, or has the wrong number of neurons
+    BadConvolution(usize),
+    /// Layer at given index (counting the input layer) reads from itself
+    /// or from a layer after it
+    BadSources(usize),
+    /// Range of the output at given index has its minimum above its maximum
+    BadRange(usize),
+    /// Networks being compared are of different topologies (or, when
+    /// batched together, have different output heads)
+    TopologyMismatch,
+    /// Fitness of the genome at given index is NaN or infinite
+    BadFitness(usize),
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyTopology => write!(f, "topology must have at least two layers"),
+            Self::EmptyLayer(layer) => write!(f, "layer #{layer} has no neurons"),
+            Self::ShapeMismatch { expected, actual } => {
+                write!(f, "expected {expected} values, got {actual}")
+            }
+            Self::WrongGeneCount { expected, actual } => {
+                write!(f, "expected {expected} genes, got {actual}")
+            }
+            Self::UnsupportedLayer(layer) => write!(f, "layer #{layer} is not supported here"),
+            Self::BadConvolution(layer) => write!(f, "convolution of layer #{layer} doesn't fit its input"),
+            Self::BadSources(layer) => write!(f, "layer #{layer} can only read layers before it"),
+            Self::BadRange(output) => write!(f, "range of output #{output} is empty"),
+            Self::TopologyMismatch => write!(f, "networks have different topologies"),
+            Self::BadFitness(genome) => write!(f, "fitness of genome #{genome} is not finite"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
@@ -0,0 +1,260 @@
+use crate::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Version written into every saved network; bump it whenever the layout
+/// of `NetworkFile` changes.
+///
+/// Version 1 predates layer kinds, sources and output heads: its JSON files
+/// still load, with those left at their defaults, but bincode can't tell
+/// its layouts apart, so its binary files are rejected.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Prefix of the binary format, so `Network::load` can tell it apart from JSON.
+const BINARY_MAGIC: &[u8; 4] = b"SLNN";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Pretty-printed JSON, meant to be read and diffed by humans
+    Json,
+    /// `BINARY_MAGIC` followed by the bincode encoding of the same data
+    Binary,
+}
+
+/// On-disk representation of a `Network`: its topology plus the flat
+/// weights in the same order as `Network::weights()`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct NetworkFile {
+    version: u32,
+    topology: Vec<LayerTopology>,
+    weights: Vec<f32>,
+    #[serde(default)]
+    head: OutputHead,
+}
+
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Binary(bincode::Error),
+    UnsupportedVersion(u32),
+    /// File is well-formed, but describes an invalid network
+    Network(NetworkError),
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::Json(err) => write!(f, "malformed json: {err}"),
+            Self::Binary(err) => write!(f, "malformed binary data: {err}"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {version} (expected {FORMAT_VERSION})"
+            ),
+            Self::Network(err) => write!(f, "invalid network: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FileError {}
+
+impl From<io::Error> for FileError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FileError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<bincode::Error> for FileError {
+    fn from(err: bincode::Error) -> Self {
+        Self::Binary(err)
+    }
+}
+
+impl From<NetworkError> for FileError {
+    fn from(err: NetworkError) -> Self {
+        Self::Network(err)
+    }
+}
+
+impl From<&Network> for NetworkFile {
+    fn from(network: &Network) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            topology: network.topology(),
+            weights: network.weights(),
+            head: network.head.clone(),
+        }
+    }
+}
+
+impl TryFrom<NetworkFile> for Network {
+    type Error = FileError;
+
+    fn try_from(file: NetworkFile) -> Result<Self, Self::Error> {
+        if !(1..=FORMAT_VERSION).contains(&file.version) {
+            return Err(FileError::UnsupportedVersion(file.version));
+        }
+
+        let mut network = Self::try_from_weights(&file.topology, file.weights)?;
+        network.set_head(file.head)?;
+        Ok(network)
+    }
+}
+
+// Written by hand rather than through `#[serde(into, try_from)]`, which
+// can't be limited to `Network<f32>`
+
+impl Serialize for Network {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        NetworkFile::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let file = NetworkFile::deserialize(deserializer)?;
+        Self::try_from(file).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Network {
+    pub fn to_bytes(&self, format: FileFormat) -> Result<Vec<u8>, FileError> {
+        match format {
+            FileFormat::Json => Ok(serde_json::to_vec_pretty(self)?),
+            FileFormat::Binary => {
+                let mut bytes = BINARY_MAGIC.to_vec();
+                bincode::serialize_into(&mut bytes, self)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decodes a network, detecting whether `bytes` hold JSON or binary data.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FileError> {
+        let file: NetworkFile = match bytes.strip_prefix(BINARY_MAGIC) {
+            Some(bytes) => {
+                // the version comes first, so it can be checked before the
+                // rest gets decoded with what may be the wrong layout
+                let version: u32 = bincode::deserialize(bytes)?;
+                if version != FORMAT_VERSION {
+                    return Err(FileError::UnsupportedVersion(version));
+                }
+                bincode::deserialize(bytes)?
+            }
+            None => serde_json::from_slice(bytes)?,
+        };
+        file.try_into()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: FileFormat) -> Result<(), FileError> {
+        fs::write(path, self.to_bytes(format)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FileError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+fn test_network() -> Network {
+    let topology = [
+        LayerTopology { neurons: 3, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 2, activation: Activation::LeakyReLU(0.01), kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::Tanh, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    let weights = (0..11).map(|i| i as f32 / 10.0 - 0.5);
+    let mut network = Network::from_weights(&topology, weights);
+    network.set_head(OutputHead::Scaled(vec![OutputRange::new(0.0, 2.0)])).unwrap();
+    network
+}
+
+#[test]
+fn test_round_trip() {
+    let network = test_network();
+
+    for format in [FileFormat::Json, FileFormat::Binary] {
+        let bytes = network.to_bytes(format).unwrap();
+        let loaded = Network::from_bytes(&bytes).unwrap();
+
+        assert_eq!(loaded.topology(), network.topology());
+        assert_eq!(loaded.weights(), network.weights());
+        assert_eq!(loaded.head(), network.head());
+    }
+}
+
+#[test]
+fn test_save_and_load() {
+    let network = test_network();
+    let path = std::env::temp_dir().join(format!("lib-neural-network-{}.bin", std::process::id()));
+
+    network.save(&path, FileFormat::Binary).unwrap();
+    let loaded = Network::load(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.unwrap().weights(), network.weights());
+}
+
+#[test]
+fn test_load_rejects_bad_files() {
+    let json = r#"{"version":1,"topology":[{"neurons":2,"activation":"Identity"},{"neurons":1,"activation":"ReLU"}],"weights":[0.1,0.2]}"#;
+    assert!(matches!(
+        Network::from_bytes(json.as_bytes()),
+        Err(FileError::Network(NetworkError::WrongGeneCount { expected: 3, actual: 2 }))
+    ));
+    assert!(matches!(Network::from_bytes(b"{}"), Err(FileError::Json(_))));
+
+    let file = NetworkFile {
+        version: FORMAT_VERSION,
+        topology: test_network().topology(),
+        weights: vec![0.0; 3],
+        head: OutputHead::Raw,
+    };
+    assert!(matches!(
+        Network::try_from(file),
+        Err(FileError::Network(NetworkError::WrongGeneCount { expected: 11, actual: 3 }))
+    ));
+
+    let file = NetworkFile {
+        head: OutputHead::Clamped(Vec::new()),
+        ..NetworkFile::from(&test_network())
+    };
+    assert!(matches!(
+        Network::try_from(file),
+        Err(FileError::Network(NetworkError::ShapeMismatch { expected: 1, actual: 0 }))
+    ));
+
+    let file = NetworkFile {
+        version: FORMAT_VERSION + 1,
+        ..NetworkFile::from(&test_network())
+    };
+    assert!(matches!(
+        Network::try_from(file),
+        Err(FileError::UnsupportedVersion(_))
+    ));
+}
+
+#[test]
+fn test_load_version_1() {
+    let expected = test_network();
+
+    let network = Network::from_bytes(include_bytes!("../fixtures/network-v1.json")).unwrap();
+    assert_eq!(network.weights(), expected.weights());
+    assert_eq!(network.head(), &OutputHead::Raw);
+    approx::assert_relative_eq!(network.propagate(vec![1.0, 0.5, -0.5])[0], 0.28714326);
+
+    assert!(matches!(
+        Network::from_bytes(include_bytes!("../fixtures/network-v1.bin")),
+        Err(FileError::UnsupportedVersion(1))
+    ));
+}
@@ -0,0 +1,143 @@
+use crate::*;
+use serde::{Deserialize, Serialize};
+
+/// Post-processing of a network's output, so that whatever consumes it -
+/// e.g. an animal's actuators - receives well-formed values directly.
+///
+/// Heads are applied by `Network::propagate()` (and its variants),
+/// `NetworkBatch` and `QuantizedNetwork`; traces - and so training and
+/// plastic networks - see the last layer's raw activations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum OutputHead {
+    /// Outputs are returned as the last layer computes them
+    #[default]
+    Raw,
+    /// Outputs become probabilities of picking one of discrete actions
+    Softmax,
+    /// Every output is squashed through tanh onto its own range
+    Scaled(Vec<OutputRange>),
+    /// Every output is clamped to its own range
+    Clamped(Vec<OutputRange>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl OutputRange {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    /// `-limit..=limit`
+    pub fn symmetric(limit: f32) -> Self {
+        Self::new(-limit, limit)
+    }
+}
+
+impl OutputHead {
+    /// Checks that the head fits a network with `outputs` outputs.
+    pub(crate) fn validate(&self, outputs: usize) -> Result<(), NetworkError> {
+        let (Self::Scaled(ranges) | Self::Clamped(ranges)) = self else {
+            return Ok(());
+        };
+
+        if ranges.len() != outputs {
+            return Err(NetworkError::ShapeMismatch { expected: outputs, actual: ranges.len() });
+        }
+
+        let bad = |range: &OutputRange| range.min.is_nan() || range.max.is_nan() || range.min > range.max;
+        match ranges.iter().position(bad) {
+            Some(output) => Err(NetworkError::BadRange(output)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn apply<T: Scalar>(&self, outputs: &mut [T]) {
+        match self {
+            Self::Raw => {}
+
+            Self::Softmax => {
+                // shifted by the largest output, which keeps `exp()` in range
+                // without changing the result
+                let Some(largest) = outputs.iter().copied().reduce(T::max) else {
+                    return;
+                };
+
+                for output in outputs.iter_mut() {
+                    *output = (*output - largest).exp();
+                }
+
+                let sum: T = outputs.iter().copied().sum();
+                for output in outputs.iter_mut() {
+                    *output = *output / sum;
+                }
+            }
+
+            Self::Scaled(ranges) => {
+                let two = T::ONE + T::ONE;
+
+                for (output, range) in outputs.iter_mut().zip(ranges) {
+                    let (min, max) = (T::from_f64(range.min as f64), T::from_f64(range.max as f64));
+                    *output = min + (max - min) * (output.tanh() + T::ONE) / two;
+                }
+            }
+
+            Self::Clamped(ranges) => {
+                for (output, range) in outputs.iter_mut().zip(ranges) {
+                    let (min, max) = (T::from_f64(range.min as f64), T::from_f64(range.max as f64));
+
+                    if *output < min {
+                        *output = min;
+                    } else if *output > max {
+                        *output = max;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_softmax() {
+    let mut outputs = [1.0f32, 2.0, 3.0];
+    OutputHead::Softmax.apply(&mut outputs);
+
+    approx::assert_relative_eq!(outputs.iter().sum::<f32>(), 1.0);
+    approx::assert_relative_eq!(outputs[2] / outputs[1], 1.0f32.exp());
+    assert!(outputs[0] < outputs[1] && outputs[1] < outputs[2]);
+
+    // wouldn't fit `f32` without the shift
+    let mut outputs = [1000.0f32, 1000.0];
+    OutputHead::Softmax.apply(&mut outputs);
+    assert_eq!(outputs, [0.5, 0.5]);
+}
+
+#[test]
+fn test_ranges() {
+    let ranges = vec![OutputRange::symmetric(0.5), OutputRange::new(2.0, 4.0)];
+
+    let mut outputs = [0.0f32, 0.0];
+    OutputHead::Scaled(ranges.clone()).apply(&mut outputs);
+    assert_eq!(outputs, [0.0, 3.0]);
+
+    let mut outputs = [100.0f32, -100.0];
+    OutputHead::Scaled(ranges.clone()).apply(&mut outputs);
+    assert_eq!(outputs, [0.5, 2.0]);
+
+    let mut outputs = [-0.7f32, 3.5];
+    OutputHead::Clamped(ranges.clone()).apply(&mut outputs);
+    assert_eq!(outputs, [-0.5, 3.5]);
+
+    assert_eq!(OutputHead::Clamped(ranges.clone()).validate(2), Ok(()));
+    assert_eq!(
+        OutputHead::Scaled(ranges).validate(3),
+        Err(NetworkError::ShapeMismatch { expected: 3, actual: 2 })
+    );
+    assert_eq!(
+        OutputHead::Clamped(vec![OutputRange::new(1.0, 0.0)]).validate(1),
+        Err(NetworkError::BadRange(0))
+    );
+}
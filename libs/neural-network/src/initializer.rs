@@ -0,0 +1,119 @@
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+
+/// Strategy for picking the starting weights of a random network.
+///
+/// `fan_in` is the number of values a neuron receives (inputs plus, for
+/// recurrent layers, context) and `fan_out` the number of neurons in its layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Initializer {
+    /// Biases and weights drawn uniformly from `low..=high`
+    Uniform { low: f32, high: f32 },
+    /// Glorot: weights drawn uniformly from `±sqrt(6 / (fan_in + fan_out))`,
+    /// biases set to zero; suits tanh and sigmoid layers
+    Xavier,
+    /// Weights drawn from `N(0, sqrt(2 / fan_in))`, biases set to zero;
+    /// suits ReLU layers
+    He,
+    Zeros,
+    /// Biases and weights drawn from `N(0, sigma)`
+    Normal { sigma: f32 },
+}
+
+impl Default for Initializer {
+    /// What `Network::random()` has always used.
+    fn default() -> Self {
+        Self::Uniform { low: -1.0, high: 1.0 }
+    }
+}
+
+impl Initializer {
+    pub(crate) fn bias(self, rng: &mut dyn RngCore) -> f32 {
+        match self {
+            Self::Uniform { low, high } => rng.gen_range(low..=high),
+            Self::Xavier | Self::He | Self::Zeros => 0.0,
+            Self::Normal { sigma } => normal(rng, sigma),
+        }
+    }
+
+    pub(crate) fn weight(self, rng: &mut dyn RngCore, fan_in: usize, fan_out: usize) -> f32 {
+        match self {
+            Self::Uniform { low, high } => rng.gen_range(low..=high),
+            Self::Xavier => {
+                let limit = (6.0 / (fan_in + fan_out) as f32).sqrt();
+                rng.gen_range(-limit..=limit)
+            }
+            Self::He => normal(rng, (2.0 / fan_in as f32).sqrt()),
+            Self::Zeros => 0.0,
+            Self::Normal { sigma } => normal(rng, sigma),
+        }
+    }
+}
+
+fn normal(rng: &mut dyn RngCore, sigma: f32) -> f32 {
+    Normal::new(0.0, sigma)
+        .expect("standard deviation must be finite and non-negative")
+        .sample(rng)
+}
+
+#[cfg(test)]
+fn first_layer(initializer: Initializer) -> (Vec<f32>, Vec<f32>) {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let network = crate::Network::builder()
+        .input(3)
+        .layer(2, crate::Activation::ReLU)
+        .initializer(initializer)
+        .build_random(&mut rng)
+        .unwrap();
+
+    let layer = &network.layers[0];
+    (layer.biases.clone(), layer.weights.clone())
+}
+
+#[test]
+fn test_uniform() {
+    let (biases, weights) = first_layer(Initializer::default());
+
+    // same values `Network::random()` produced before initializers existed
+    assert_eq!(biases, &[-0.6255188, 0.5238807]);
+    assert_eq!(weights, &[0.67383957, 0.8181262, 0.26284897, -0.53516835, 0.069369674, -0.7648182]);
+
+    let (biases, weights) = first_layer(Initializer::Uniform { low: 0.0, high: 0.5 });
+    assert!(biases.iter().chain(&weights).all(|value| (0.0..=0.5).contains(value)));
+}
+
+#[test]
+fn test_xavier() {
+    let (biases, weights) = first_layer(Initializer::Xavier);
+
+    assert_eq!(biases, &[0.0, 0.0]);
+    assert_eq!(weights, &[-0.6852215, 0.7381542, 0.89621234, 0.2879367, 0.57388246, -0.58624756]);
+    // limit = sqrt(6 / (3 + 2))
+    assert!(weights.iter().all(|weight| weight.abs() <= 1.2f32.sqrt()));
+}
+
+#[test]
+fn test_he() {
+    let (biases, weights) = first_layer(Initializer::He);
+
+    assert_eq!(biases, &[0.0, 0.0]);
+    assert_eq!(weights, &[1.1248851, 0.3309643, -0.97674954, -1.5799594, -0.54850096, -0.8692553]);
+}
+
+#[test]
+fn test_zeros() {
+    let (biases, weights) = first_layer(Initializer::Zeros);
+
+    assert_eq!(biases, &[0.0; 2]);
+    assert_eq!(weights, &[0.0; 6]);
+}
+
+#[test]
+fn test_normal() {
+    let (biases, weights) = first_layer(Initializer::Normal { sigma: 0.1 });
+
+    assert_eq!(biases, &[0.13776973, -0.06717738]);
+    assert_eq!(weights, &[0.040534683, -0.119626895, -0.1935047, -0.1064616, -0.21489763, -0.10025622]);
+}
@@ -0,0 +1,187 @@
+use crate::*;
+
+/// Fully connected layer; `weights` is a row-major `output_size x input_size`
+/// matrix, so the weights of a single neuron are contiguous.
+///
+/// Recurrent layers additionally see a context - their own previous output
+/// (Elman) or the network's previous output (Jordan) - through another
+/// `output_size x context_size` matrix in `context_weights`; the context
+/// itself lives in `Scratch`.
+///
+/// Convolution layers instead hold one bias and one row of
+/// `Conv1d::filter_size()` weights per filter.
+///
+/// Inputs are the concatenated outputs of `sources`, see `Sources`.
+#[derive(Clone, Debug)]
+pub(crate) struct Layer<T = f32> {
+    pub(crate) input_size: usize,
+    pub(crate) weights: Vec<T>,
+    pub(crate) biases: Vec<T>,
+    pub(crate) activation: Activation,
+    pub(crate) kind: LayerKind,
+    pub(crate) sources: Sources,
+    pub(crate) context_weights: Vec<T>,
+    pub(crate) context_size: usize,
+}
+
+impl Layer {
+
+    /// Number of rows of parameters (neurons, or filters of a convolution)
+    /// a layer of given topology has, and number of weights in each of them.
+    pub(crate) fn shape(input_size: usize, topology: &LayerTopology) -> (usize, usize) {
+        match topology.kind {
+            LayerKind::Conv1d(conv) => (conv.channels, conv.filter_size()),
+            _ => (topology.neurons, input_size),
+        }
+    }
+
+    /// Number of values fed back into a layer of given kind, where
+    /// `network_outputs` is the size of the network's last layer.
+    pub(crate) fn context_size(kind: LayerKind, neurons: usize, network_outputs: usize) -> usize {
+        match kind {
+            LayerKind::Dense | LayerKind::Conv1d(_) => 0,
+            LayerKind::Elman => neurons,
+            LayerKind::Jordan => network_outputs,
+        }
+    }
+
+    pub(crate) fn random(rng: &mut dyn rand::RngCore, input_size: usize, topology: &LayerTopology, network_outputs: usize, initializer: Initializer) -> Self {
+        let (rows, row_size) = Self::shape(input_size, topology);
+        let context_size = Self::context_size(topology.kind, topology.neurons, network_outputs);
+
+        let mut weights = Vec::with_capacity(row_size * rows);
+        let mut biases = Vec::with_capacity(rows);
+        let mut context_weights = Vec::with_capacity(context_size * rows);
+
+        let fan_in = row_size + context_size;
+
+        // drawn neuron by neuron, bias first, so seeded networks stay the
+        // same as back when every neuron owned its own `Vec`
+        for _ in 0..rows {
+            biases.push(initializer.bias(rng));
+            weights.extend((0..row_size).map(|_| initializer.weight(rng, fan_in, rows)));
+            context_weights.extend((0..context_size).map(|_| initializer.weight(rng, fan_in, rows)));
+        }
+
+        Self {
+            input_size,
+            weights,
+            biases,
+            activation: topology.activation,
+            kind: topology.kind,
+            sources: topology.sources,
+            context_weights,
+            context_size,
+        }
+    }
+}
+
+impl<T: Scalar> Layer<T> {
+    pub(crate) fn output_size(&self) -> usize {
+        match self.kind {
+            LayerKind::Conv1d(conv) => conv.output_size(self.input_size).expect("convolution doesn't fit its input"),
+            _ => self.biases.len(),
+        }
+    }
+
+    /// Writes this layer's response into `output`, which must hold exactly
+    /// `output_size()` values; Elman layers also store it in `context`.
+    pub(crate) fn propagate_into(&self, input: &[T], context: &mut [T], output: &mut [T]) {
+        self.sums_into(input, context, output);
+
+        for output in output.iter_mut() {
+            *output = self.activation.apply(*output);
+        }
+
+        if self.kind == LayerKind::Elman {
+            context.copy_from_slice(output);
+        }
+    }
+
+    /// Computes every neuron's weighted sum (including bias), before it goes
+    /// through the activation function; an empty `context` stands for a
+    /// blank one.
+    pub(crate) fn sums_into(&self, input: &[T], context: &[T], sums: &mut [T]) {
+        debug_assert_eq!(input.len(), self.input_size);
+        debug_assert_eq!(sums.len(), self.output_size());
+
+        if let LayerKind::Conv1d(conv) = self.kind {
+            conv.sums_into(&self.weights, &self.biases, input, sums);
+            return;
+        }
+
+        let rows = self.weights.chunks_exact(self.input_size);
+        for (neuron, ((sum, row), &bias)) in sums.iter_mut().zip(rows).zip(&self.biases).enumerate() {
+            *sum = row.iter().zip(input).map(|(&weight, &input)| weight * input).sum();
+
+            if !context.is_empty() {
+                let row = &self.context_weights[neuron * self.context_size..][..self.context_size];
+                *sum += row.iter().zip(context).map(|(&weight, &context)| weight * context).sum::<T>();
+            }
+
+            *sum += bias;
+        }
+    }
+
+    /// Genes are laid out neuron (or filter) by neuron: the bias followed by
+    /// its weights and then, for recurrent layers, its context weights.
+    pub(crate) fn weights(&self) -> impl Iterator<Item = T> + '_ {
+        let context_size = self.context_size;
+        let row_size = self.weights.len() / self.biases.len();
+
+        (0..self.biases.len()).flat_map(move |neuron| {
+            let row = &self.weights[neuron * row_size..][..row_size];
+            let context_row = &self.context_weights[neuron * context_size..][..context_size];
+
+            std::iter::once(self.biases[neuron])
+                .chain(row.iter().copied())
+                .chain(context_row.iter().copied())
+        })
+    }
+
+    pub(crate) fn from_weights(input_size: usize, topology: &LayerTopology, network_outputs: usize, weights: &mut dyn Iterator<Item = T>) -> Self {
+        let (rows, row_size) = Layer::shape(input_size, topology);
+        let context_size = Layer::context_size(topology.kind, topology.neurons, network_outputs);
+
+        let mut matrix = Vec::with_capacity(row_size * rows);
+        let mut biases = Vec::with_capacity(rows);
+        let mut context_weights = Vec::with_capacity(context_size * rows);
+
+        for _ in 0..rows {
+            biases.push(weights.next().expect("got not enough weights"));
+            for _ in 0..row_size {
+                matrix.push(weights.next().expect("got not enough weights"));
+            }
+            for _ in 0..context_size {
+                context_weights.push(weights.next().expect("got not enough weights"));
+            }
+        }
+
+        Self {
+            input_size,
+            weights: matrix,
+            biases,
+            activation: topology.activation,
+            kind: topology.kind,
+            sources: topology.sources,
+            context_weights,
+            context_size,
+        }
+    }
+
+    /// Converts every parameter to another scalar type.
+    pub(crate) fn cast<U: Scalar>(&self) -> Layer<U> {
+        let cast = |values: &[T]| values.iter().map(|value| U::from_f64(value.to_f64())).collect();
+
+        Layer {
+            input_size: self.input_size,
+            weights: cast(&self.weights),
+            biases: cast(&self.biases),
+            activation: self.activation,
+            kind: self.kind,
+            sources: self.sources,
+            context_weights: cast(&self.context_weights),
+            context_size: self.context_size,
+        }
+    }
+}
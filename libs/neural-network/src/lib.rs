@@ -1,147 +1,368 @@
-use rand::Rng;
+pub use self::{activation::*, batch::*, builder::*, conv::*, ctrnn::*, distance::*, error::*, file::*, head::*, initializer::*, neat::*, plasticity::*, prune::*, quantize::*, scalar::*, sources::*, trace::*, train::*};
+
+mod activation;
+mod batch;
+mod builder;
+mod conv;
+mod ctrnn;
+mod diagram;
+mod distance;
+mod error;
+mod file;
+mod head;
+mod initializer;
+mod neat;
+mod plasticity;
+mod prune;
+mod quantize;
+mod scalar;
+mod sources;
+mod trace;
+mod train;
+mod layer;
+
+use self::layer::*;
+use serde::{Deserialize, Serialize};
 #[cfg(test)]
 use rand::SeedableRng;
 #[cfg(test)]
 use rand_chacha::ChaCha8Rng;
 
-#[derive(Debug)]
-pub struct Network{
-    layers: Vec<Layer>,
+/// Layered network computing with `T`, see `Scalar`.
+///
+/// Networks are built in `f32` - by `random()`, `from_weights()` or
+/// `builder()` - and can be `cast()` to other scalar types from there; most
+/// extras (training, batching, files and so on) work with `f32` only.
+#[derive(Clone, Debug)]
+pub struct Network<T = f32> {
+    layers: Vec<Layer<T>>,
+    head: OutputHead,
 }
 
+/// Describes one layer of a network; for the input layer `activation` is
+/// unused, since inputs are fed to the first hidden layer as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LayerTopology {
     pub neurons: usize,
+    pub activation: Activation,
+    #[serde(default)]
+    pub kind: LayerKind,
+    /// Earlier layers this one reads; unused for the input layer
+    #[serde(default)]
+    pub sources: Sources,
 }
 
-#[derive(Debug)]
-struct Layer{
-    neurons: Vec<Neuron>,
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerKind {
+    #[default]
+    Dense,
+    /// Recurrent layer that also sees its own output from the previous call
+    Elman,
+    /// Recurrent layer that also sees the network's output from the previous call
+    Jordan,
+    /// Filters with shared weights slid along the previous layer
+    Conv1d(Conv1d),
 }
 
-#[derive(Debug)]
-struct Neuron{
-    bias: f32,
-    weights: Vec<f32>,
-}
+impl<T: Scalar> Network<T> {
+    /// Responds to `inputs`; recurrent layers start from a blank context, so
+    /// to have them remember across calls use `propagate_into()` instead.
+    pub fn propagate(&self, inputs: Vec<T>) -> Vec<T> {
+        let mut scratch = Scratch::new(self);
+        self.propagate_into(&inputs, &mut scratch).to_vec()
+    }
 
-impl Network {
-    pub fn new() -> Self{
-        todo!()
+    /// Like `propagate()`, but reports inputs of wrong size instead of panicking.
+    pub fn try_propagate(&self, inputs: Vec<T>) -> Result<Vec<T>, NetworkError> {
+        let expected = self.layers[0].input_size;
+        if inputs.len() != expected {
+            return Err(NetworkError::ShapeMismatch { expected, actual: inputs.len() });
+        }
+
+        Ok(self.propagate(inputs))
     }
 
-    pub fn propagate(&self, mut inputs: Vec<f32>) -> Vec<f32> {
+    /// Allocation-free version of `propagate()`: intermediate and final
+    /// values are kept in `scratch`, which can be reused across calls (and
+    /// networks) - it only grows when it meets a wider layer than before.
+    ///
+    /// `scratch` also holds what recurrent layers remember, see `Scratch`.
+    pub fn propagate_into<'a>(&self, input: &[T], scratch: &'a mut Scratch<T>) -> &'a [T] {
+        assert_eq!(input.len(), self.layers[0].input_size);
+        scratch.reserve(self.total_width(), self.max_input_size(), self.context_size());
+
+        // every layer's output is kept, right after the ones before it, as
+        // later layers may read any of them
+        scratch.values[..input.len()].copy_from_slice(input);
+        let mut offset = input.len();
+        let mut context_offset = 0;
+
+        for idx in 0..self.layers.len() {
+            let (done, rest) = scratch.values.split_at_mut(offset);
+            let input_size = self.layers[idx].input_size;
+
+            let input = if self.layers[idx].sources.is_previous() {
+                &done[offset - input_size..]
+            } else {
+                let mut len = 0;
+                for source in self.sources(idx) {
+                    let (start, width) = (self.offset(source), self.width(source));
+                    scratch.staging[len..][..width].copy_from_slice(&done[start..][..width]);
+                    len += width;
+                }
+                &scratch.staging[..len]
+            };
+
+            let layer = &self.layers[idx];
+            let width = layer.output_size();
+            let context = &mut scratch.context[context_offset..][..layer.context_size];
+            layer.propagate_into(input, context, &mut rest[..width]);
+            offset += width;
+            context_offset += layer.context_size;
+        }
+
+        // Jordan layers remember the raw output, before the head
+        let output = &mut scratch.values[offset - self.width(self.layers.len())..offset];
+        let mut context_offset = 0;
         for layer in &self.layers {
-            inputs = layer.propagate(inputs);
+            if layer.kind == LayerKind::Jordan {
+                scratch.context[context_offset..][..layer.context_size].copy_from_slice(output);
+            }
+            context_offset += layer.context_size;
         }
-        return inputs;
+
+        self.head.apply(output);
+        output
+    }
+
+    pub fn weights(&self) -> Vec<T> {
+        self.layers.iter().flat_map(Layer::weights).collect()
+    }
+
+    /// Reconstructs the topology this network was built from; the input
+    /// layer is reported with `Activation::Identity`.
+    pub fn topology(&self) -> Vec<LayerTopology> {
+        let mut topology = Vec::with_capacity(self.layers.len() + 1);
+        topology.push(LayerTopology {
+            neurons: self.layers[0].input_size,
+            activation: Activation::Identity,
+            kind: LayerKind::Dense,
+            sources: Sources::default(),
+        });
+
+        for layer in &self.layers {
+            topology.push(LayerTopology {
+                neurons: layer.output_size(),
+                activation: layer.activation,
+                kind: layer.kind,
+                sources: layer.sources,
+            });
+        }
+        topology
+    }
+
+    pub fn head(&self) -> &OutputHead {
+        &self.head
+    }
+
+    /// Replaces the head post-processing this network's output; `Scaled`
+    /// and `Clamped` heads need a range for every output.
+    pub fn set_head(&mut self, head: OutputHead) -> Result<(), NetworkError> {
+        head.validate(self.width(self.layers.len()))?;
+        self.head = head;
+        Ok(())
+    }
+
+    /// Same network computing with another scalar type; every parameter is
+    /// rounded to the nearest value `U` can represent.
+    pub fn cast<U: Scalar>(&self) -> Network<U> {
+        Network {
+            layers: self.layers.iter().map(Layer::cast).collect(),
+            head: self.head.clone(),
+        }
+    }
+
+    /// Layers (counting the input layer as #0) read by `self.layers[idx]`.
+    pub(crate) fn sources(&self, idx: usize) -> impl Iterator<Item = usize> {
+        self.layers[idx].sources.resolve(idx + 1)
+    }
+
+    /// Number of values layer #`layer` (counting the input layer) outputs.
+    pub(crate) fn width(&self, layer: usize) -> usize {
+        match layer {
+            0 => self.layers[0].input_size,
+            _ => self.layers[layer - 1].output_size(),
+        }
+    }
+
+    /// Where layer #`layer`'s output starts within `Scratch::values`.
+    fn offset(&self, layer: usize) -> usize {
+        (0..layer).map(|layer| self.width(layer)).sum()
+    }
+
+    fn total_width(&self) -> usize {
+        self.offset(self.layers.len() + 1)
+    }
+
+    fn max_input_size(&self) -> usize {
+        self.layers.iter().map(|layer| layer.input_size).max().unwrap_or(0)
+    }
+
+    /// Number of values all recurrent layers remember together.
+    fn context_size(&self) -> usize {
+        self.layers.iter().map(|layer| layer.context_size).sum()
+    }
+}
+
+impl Network {
+    pub fn builder() -> NetworkBuilder {
+        NetworkBuilder::new()
     }
 
     pub fn random(rng: &mut dyn rand::RngCore, layers: &[LayerTopology]) -> Self {
+        Self::random_with(rng, layers, Initializer::default())
+    }
+
+    pub fn random_with(rng: &mut dyn rand::RngCore, layers: &[LayerTopology], initializer: Initializer) -> Self {
         assert!(layers.len() > 1);
 
+        let network_outputs = layers[layers.len() - 1].neurons;
         let mut built_layers = Vec::new();
 
-        for adj_layers in layers.windows(2) {
-            let input_neurons: usize = adj_layers[0].neurons;
-            let output_neurons: usize = adj_layers[1].neurons;
+        for (idx, layer) in layers.iter().enumerate().skip(1) {
+            let input_neurons = Self::input_size(layers, idx);
 
-            built_layers.push(Layer::random(rng, input_neurons, output_neurons));
+            built_layers.push(Layer::random(rng, input_neurons, layer, network_outputs, initializer));
         }
 
-        Self { layers: built_layers }
+        Self { layers: built_layers, head: OutputHead::Raw }
     }
 
-    pub fn weights(&self) -> Vec<f32>{
-        let mut weights = Vec::new();
-        for layer in &self.layers {
-            for neuron in &layer.neurons {
-                weights.push(neuron.bias);
-    
-                for weight in &neuron.weights {
-                    weights.push(*weight);
-                }
-            }
-        }
-        weights
+    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Self {
+        Self::try_from_weights(layers, weights).unwrap_or_else(|err| panic!("{err}"))
     }
 
-    pub fn from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Self {
-        assert!(layers.len() > 1);
+    pub fn try_from_weights(layers: &[LayerTopology], weights: impl IntoIterator<Item = f32>) -> Result<Self, NetworkError> {
+        Self::validate(layers)?;
+
+        let weights: Vec<f32> = weights.into_iter().collect();
+        let expected = Self::weight_count(layers);
+        if weights.len() != expected {
+            return Err(NetworkError::WrongGeneCount { expected, actual: weights.len() });
+        }
 
+        let network_outputs = layers[layers.len() - 1].neurons;
         let mut weights = weights.into_iter();
-        let layers = layers.windows(2).map(|layers| {
+        let built_layers = (1..layers.len()).map(|idx| {
                 Layer::from_weights(
-                    layers[0].neurons,
-                    layers[1].neurons,
+                    Self::input_size(layers, idx),
+                    &layers[idx],
+                    network_outputs,
                     &mut weights,
                 )
             }).collect();
 
-        if weights.next().is_some() {
-            panic!("got too many weights");
-        }
-
-        Self { layers }
+        Ok(Self { layers: built_layers, head: OutputHead::Raw })
     }
-}
 
-impl Layer{
-    fn propagate(&self, inputs: Vec<f32>) -> Vec<f32>{
-        let mut outputs = Vec::with_capacity(self.neurons.len());
-        for neuron in &self.neurons {
-            let output = neuron.propagate(&inputs);
-            outputs.push(output);
+    pub(crate) fn validate(layers: &[LayerTopology]) -> Result<(), NetworkError> {
+        if layers.len() < 2 {
+            return Err(NetworkError::EmptyTopology);
         }
 
-        outputs
-    }
+        for (idx, layer) in layers.iter().enumerate().skip(1) {
+            if layer.sources.iter().any(|source| source >= idx) {
+                return Err(NetworkError::BadSources(idx));
+            }
 
-    pub fn random(rng: &mut dyn rand::RngCore, input_neurons: usize, output_neurons: usize) -> Self {
-        let mut neurons = Vec::with_capacity(output_neurons);
-        for _ in 0..output_neurons {
-            neurons.push(Neuron::random(rng, input_neurons));
+            if let LayerKind::Conv1d(conv) = layer.kind {
+                if conv.output_size(Self::input_size(layers, idx)) != Some(layer.neurons) {
+                    return Err(NetworkError::BadConvolution(idx));
+                }
+            }
         }
 
-        Self { neurons }
+        match layers.iter().position(|layer| layer.neurons == 0) {
+            Some(layer) => Err(NetworkError::EmptyLayer(layer)),
+            None => Ok(()),
+        }
     }
 
-    pub fn from_weights(input_size: usize, output_size: usize, weights: &mut dyn Iterator<Item = f32>) -> Self {
-        let neurons = (0..output_size).map(|_| Neuron::from_weights(input_size, weights)).collect();
-        Self { neurons }
+    /// Number of genes `from_weights()` expects for given topology.
+    pub fn weight_count(layers: &[LayerTopology]) -> usize {
+        Self::neuron_genes(layers).iter().sum()
     }
-}
-
-impl Neuron{
-    fn propagate(&self, inputs: &[f32]) -> f32{
-        assert_eq!(inputs.len(), self.weights.len());
 
-        let mut output: f32 = 0.0;
-        for (&input, &weight) in inputs.iter().zip(&self.weights) { //faster than without zipping since outofbounds checking
-            output += input * weight;
-        }
-        output += self.bias;
-
-        return output.max(0.0);
+    /// Number of genes of every neuron (or convolution filter) of given
+    /// topology, which `weights()` lays out one after another - e.g. for
+    /// crossovers that keep neurons whole.
+    pub fn neuron_genes(layers: &[LayerTopology]) -> Vec<usize> {
+        let network_outputs = layers.last().map_or(0, |layer| layer.neurons);
+
+        // every neuron holds a bias, one weight per neuron of the layers it
+        // reads and one per value of its context; convolutions instead have
+        // a bias and a kernel's worth of weights per filter
+        layers.iter().enumerate().skip(1).flat_map(|(idx, layer)| {
+            let (rows, row_size) = Layer::shape(Self::input_size(layers, idx), layer);
+            let context_size = Layer::context_size(layer.kind, layer.neurons, network_outputs);
+            std::iter::repeat_n(1 + row_size + context_size, rows)
+        }).collect()
     }
 
-    pub fn random(rng: &mut dyn rand::RngCore, output_size: usize) -> Self {
-        let bias = rng.gen_range(-1.0..=1.0);
+    /// Number of values layer #`idx` of given topology reads; sources out
+    /// of range (which `validate()` rejects) are skipped.
+    fn input_size(layers: &[LayerTopology], idx: usize) -> usize {
+        layers[idx].sources.resolve(idx).filter_map(|source| layers.get(source)).map(|source| source.neurons).sum()
+    }
+}
 
-        let weights = (0..output_size)
-            .map(|_| rng.gen_range(-1.0..=1.0))
-            .collect();
+/// Reusable buffers for `Network::propagate_into()`.
+///
+/// They also hold the context of recurrent layers - their own or the
+/// network's previous output - which carries over from one call to the
+/// next; a network with recurrent layers needs a scratch of its own to
+/// remember anything.
+#[derive(Clone, Debug)]
+pub struct Scratch<T = f32> {
+    /// Inputs, followed by the output of every layer
+    values: Vec<T>,
+    /// Inputs of the current layer, when it reads more than the layer
+    /// right before it
+    staging: Vec<T>,
+    /// Context of every recurrent layer, one after another
+    context: Vec<T>,
+}
 
-        Self { bias, weights }
+impl<T> Default for Scratch<T> {
+    fn default() -> Self {
+        Self { values: Vec::new(), staging: Vec::new(), context: Vec::new() }
     }
+}
 
-    pub fn from_weights(output_neurons: usize, weights: &mut dyn Iterator<Item = f32>) -> Self {
-        let bias = weights.next().expect("got not enough weights");
+impl<T: Scalar> Scratch<T> {
+    /// Creates buffers big enough for `network`, so that the first call to
+    /// `propagate_into()` doesn't have to allocate either.
+    pub fn new(network: &Network<T>) -> Self {
+        let mut scratch = Self::default();
+        scratch.reserve(network.total_width(), network.max_input_size(), network.context_size());
+        scratch
+    }
 
-        let weights = (0..output_neurons)
-            .map(|_| weights.next().expect("got not enough weights"))
-            .collect();
+    /// Forgets everything recurrent layers remember from previous calls.
+    pub fn reset_state(&mut self) {
+        self.context.fill(T::ZERO);
+    }
 
-        Self { bias, weights }
+    fn reserve(&mut self, values: usize, staging: usize, context: usize) {
+        if self.values.len() < values {
+            self.values.resize(values, T::ZERO);
+        }
+        if self.staging.len() < staging {
+            self.staging.resize(staging, T::ZERO);
+        }
+        if self.context.len() < context {
+            self.context.resize(context, T::ZERO);
+        }
     }
 }
 
@@ -150,31 +371,285 @@ fn test() {
     // Because we always use the same seed, our `rng` in here will
     // always return the same set of values
     let mut rng = ChaCha8Rng::from_seed(Default::default());
-    let neuron = Neuron::random(&mut rng, 4);
+    let topology = LayerTopology { neurons: 1, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() };
+    let layer = Layer::random(&mut rng, 4, &topology, 1, Initializer::default());
 
-    assert_eq!(neuron.bias, -0.6255188);
-    assert_eq!(neuron.weights, &[0.67383957, 0.8181262, 0.26284897, 0.5238807]);
+    assert_eq!(layer.biases, &[-0.6255188]);
+    assert_eq!(layer.weights, &[0.67383957, 0.8181262, 0.26284897, 0.5238807]);
 }
 
 #[test]
 fn test2() {
-    let neuron = Neuron {
-        bias: 0.5,
+    let layer = Layer {
+        input_size: 2,
         weights: vec![-0.3, 0.8],
+        biases: vec![0.5],
+        activation: Activation::ReLU,
+        kind: LayerKind::Dense,
+        sources: Sources::default(),
+        context_weights: Vec::new(),
+        context_size: 0,
     };
+    let mut output = [f32::NAN];
 
     // Ensures `.max()` (our ReLU) works:
-    approx::assert_relative_eq!(
-        neuron.propagate(&[-10.0, -10.0]),
-        0.0,
-    );
+    layer.propagate_into(&[-10.0, -10.0], &mut [], &mut output);
+    approx::assert_relative_eq!(output[0], 0.0);
 
     // `0.5` and `1.0` chosen by a fair dice roll:
+    layer.propagate_into(&[0.5, 1.0], &mut [], &mut output);
     approx::assert_relative_eq!(
-        neuron.propagate(&[0.5, 1.0]),
+        output[0],
         (-0.3 * 0.5) + (0.8 * 1.0) + 0.5,
     );
 
     // We could've written `1.15` right away, but showing the entire
     // formula makes our intentions clearer
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_activation_per_layer() {
+    let topology = [
+        LayerTopology { neurons: 2, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::Tanh, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    // hidden: relu(0.0 + 1.0 * 1.0 + 1.0 * 2.0) = 3.0
+    // output: tanh(-1.0 + -1.0 * 3.0) = tanh(-4.0)
+    let network = Network::from_weights(&topology, [0.0, 1.0, 1.0, -1.0, -1.0]);
+
+    approx::assert_relative_eq!(network.propagate(vec![1.0, 2.0])[0], (-4.0f32).tanh());
+    assert_eq!(network.topology(), topology);
+}
+
+#[test]
+fn test_flat_layout_keeps_gene_order() {
+    let topology = [
+        LayerTopology { neurons: 3, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 2, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    // bias, then weights, neuron by neuron
+    let genes: Vec<f32> = (1..=11).map(|gene| gene as f32).collect();
+    let network = Network::from_weights(&topology, genes.clone());
+
+    assert_eq!(network.layers[0].biases, &[1.0, 5.0]);
+    assert_eq!(network.layers[0].weights, &[2.0, 3.0, 4.0, 6.0, 7.0, 8.0]);
+    assert_eq!(network.layers[1].biases, &[9.0]);
+    assert_eq!(network.layers[1].weights, &[10.0, 11.0]);
+    assert_eq!(network.weights(), genes);
+}
+
+#[test]
+fn test_propagate_into_reuses_scratch() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let topology = [
+        LayerTopology { neurons: 4, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 8, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 2, activation: Activation::Tanh, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    let network = Network::random(&mut rng, &topology);
+    let mut scratch = Scratch::new(&network);
+    let capacity = scratch.values.capacity();
+
+    for input in [[0.1, 0.2, 0.3, 0.4], [1.0, -1.0, 0.5, 0.0]] {
+        let expected = network.propagate(input.to_vec());
+        assert_eq!(network.propagate_into(&input, &mut scratch), expected);
+    }
+    assert_eq!(scratch.values.capacity(), capacity);
+}
+
+#[test]
+fn test_elman_layer_remembers() {
+    let topology = [
+        LayerTopology { neurons: 1, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::Identity, kind: LayerKind::Elman, sources: Sources::default() },
+    ];
+    // bias, input weight, context weight
+    let network = Network::from_weights(&topology, [0.0, 1.0, 0.5]);
+    let mut scratch = Scratch::new(&network);
+    assert_eq!(Network::weight_count(&topology), 3);
+    assert_eq!(network.weights(), &[0.0, 1.0, 0.5]);
+
+    assert_eq!(network.propagate_into(&[1.0], &mut scratch), &[1.0]);
+    assert_eq!(network.propagate_into(&[0.0], &mut scratch), &[0.5]);
+    assert_eq!(network.propagate_into(&[0.0], &mut scratch), &[0.25]);
+
+    // the network itself keeps no state
+    assert_eq!(network.propagate(vec![0.0]), &[0.0]);
+
+    scratch.reset_state();
+    assert_eq!(network.propagate_into(&[0.0], &mut scratch), &[0.0]);
+}
+
+#[test]
+fn test_jordan_layer_sees_network_output() {
+    let topology = [
+        LayerTopology { neurons: 1, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::Identity, kind: LayerKind::Jordan, sources: Sources::default() },
+        LayerTopology { neurons: 2, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+    // hidden neuron: bias, input weight, then one context weight per network output;
+    // output neurons: bias, hidden weight
+    let genes = [0.0, 1.0, 1.0, -1.0, 0.0, 2.0, 1.0, 1.0];
+    let network = Network::from_weights(&topology, genes);
+    let mut scratch = Scratch::new(&network);
+    assert_eq!(Network::weight_count(&topology), genes.len());
+    assert_eq!(Network::neuron_genes(&topology), &[4, 2, 2]);
+
+    assert_eq!(network.propagate_into(&[1.0], &mut scratch), &[2.0, 2.0]);
+    // hidden: 1.0 * 1.0 + 1.0 * 2.0 - 1.0 * 2.0
+    assert_eq!(network.propagate_into(&[1.0], &mut scratch), &[2.0, 2.0]);
+    // hidden: 0.0 + 2.0 - 2.0 = 0.0 -> outputs are just the biases
+    assert_eq!(network.propagate_into(&[0.0], &mut scratch), &[0.0, 1.0]);
+}
+
+#[test]
+fn test_fallible_api() {
+    let topology = [
+        LayerTopology { neurons: 2, activation: Activation::Identity, kind: LayerKind::Dense, sources: Sources::default() },
+        LayerTopology { neurons: 1, activation: Activation::ReLU, kind: LayerKind::Dense, sources: Sources::default() },
+    ];
+
+    assert_eq!(
+        Network::try_from_weights(&topology, [0.0; 4]).unwrap_err(),
+        NetworkError::WrongGeneCount { expected: 3, actual: 4 }
+    );
+    assert_eq!(
+        Network::try_from_weights(&topology, [0.0; 2]).unwrap_err(),
+        NetworkError::WrongGeneCount { expected: 3, actual: 2 }
+    );
+    assert_eq!(
+        Network::try_from_weights(&topology[..1], []).unwrap_err(),
+        NetworkError::EmptyTopology
+    );
+
+    let network = Network::try_from_weights(&topology, [0.5, 1.0, 1.0]).unwrap();
+    assert_eq!(network.try_propagate(vec![1.0, 2.0]), Ok(vec![3.5]));
+    assert_eq!(
+        network.try_propagate(vec![1.0]),
+        Err(NetworkError::ShapeMismatch { expected: 2, actual: 1 })
+    );
+}
+
+#[test]
+fn test_generic_scalars() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let network = Network::builder()
+        .input(3)
+        .layer(4, Activation::Tanh)
+        .layer_of_kind(2, Activation::Sigmoid, LayerKind::Elman)
+        .build_random(&mut rng)
+        .unwrap();
+    let precise: Network<f64> = network.cast();
+    let fixed: Network<Fixed> = network.cast();
+
+    assert_eq!(fixed.topology(), network.topology());
+    for (weight, fixed) in network.weights().iter().zip(fixed.weights()) {
+        approx::assert_relative_eq!(fixed.to_f64(), *weight as f64, epsilon = 1e-5);
+    }
+
+    let inputs = [0.5, -0.25, 0.75];
+    let mut scratch = Scratch::new(&network);
+    let mut precise_scratch = Scratch::new(&precise);
+    let mut fixed_scratch = Scratch::new(&fixed);
+    for _ in 0..3 {
+        let expected = network.propagate_into(&inputs, &mut scratch);
+        let precise = precise.propagate_into(&inputs.map(|input| input as f64), &mut precise_scratch);
+        let fixed = fixed.propagate_into(&inputs.map(|input| Fixed::from_f64(input as f64)), &mut fixed_scratch);
+
+        for ((expected, precise), fixed) in expected.iter().zip(precise).zip(fixed) {
+            approx::assert_relative_eq!(*precise, *expected as f64, epsilon = 1e-6);
+            approx::assert_relative_eq!(fixed.to_f64(), *expected as f64, epsilon = 1e-3);
+        }
+    }
+
+    // integer arithmetic only, so these are the same on every machine
+    let bits: Vec<i32> = fixed.propagate_into(&[Fixed::ONE; 3], &mut fixed_scratch).iter().map(|value| value.to_bits()).collect();
+    assert_eq!(bits, &[22184, 27240]);
+}
+
+#[test]
+fn test_skip_connections() {
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::ReLU)
+        .layer(1, Activation::Identity)
+        .sources([0, 1])
+        .build_from_weights([
+            0.0, 1.0, 1.0,
+            -1.0, 1.0, 0.0,
+            // bias, inputs, then the hidden layer
+            0.5, 2.0, -1.0, 1.0, 3.0,
+        ])
+        .unwrap();
+
+    // hidden: relu(1 + 2) = 3, relu(-1 + 1) = 0
+    // output: 0.5 + 2 * 1 - 1 * 2 + 1 * 3 + 3 * 0
+    assert_eq!(network.propagate(vec![1.0, 2.0]), &[3.5]);
+    assert_eq!(network.propagate_traced(vec![1.0, 2.0]).output(), &[3.5]);
+    assert_eq!(Network::weight_count(&network.topology()), 11);
+    assert_eq!(Network::neuron_genes(&network.topology()), &[3, 3, 5]);
+
+    let json = serde_json::to_string(&network).unwrap();
+    assert!(json.contains(r#""sources":[0,1]"#));
+    assert_eq!(serde_json::from_str::<Network>(&json).unwrap().topology(), network.topology());
+}
+
+#[test]
+fn test_layer_graph() {
+    // two parallel branches, picking either input, joined by the output
+    let network = Network::builder()
+        .input(2)
+        .layer(1, Activation::Identity)
+        .layer(1, Activation::Identity)
+        .sources([0])
+        .layer(1, Activation::Identity)
+        .sources([1, 2])
+        .build_from_weights([0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, -1.0])
+        .unwrap();
+    let mut scratch = Scratch::new(&network);
+
+    assert_eq!(network.propagate_into(&[5.0, 2.0], &mut scratch), &[3.0]);
+    assert_eq!(network.propagate_into(&[1.0, 4.0], &mut scratch), &[-3.0]);
+
+    assert_eq!(
+        Network::builder().input(1).layer(1, Activation::ReLU).sources([1]).build_from_weights([0.0; 3]).unwrap_err(),
+        NetworkError::BadSources(1)
+    );
+    assert_eq!(
+        Network::builder()
+            .input(1)
+            .layer(1, Activation::ReLU)
+            .layer(1, Activation::ReLU)
+            .sources([0, 3])
+            .build_from_weights([0.0; 4])
+            .unwrap_err(),
+        NetworkError::BadSources(2)
+    );
+}
+
+#[test]
+fn test_output_head() {
+    let ranges = vec![OutputRange::symmetric(0.5), OutputRange::new(0.0, 10.0)];
+    let mut network = Network::builder()
+        .input(2)
+        .layer(2, Activation::Identity)
+        .head(OutputHead::Scaled(ranges.clone()))
+        .build_from_weights([0.0, 1.0, 0.0, 0.0, 0.0, 1.0])
+        .unwrap();
+    let mut scratch = Scratch::new(&network);
+
+    assert_eq!(network.propagate(vec![0.0, 0.0]), &[0.0, 5.0]);
+    assert_eq!(network.propagate_into(&[100.0, -100.0], &mut scratch), &[0.5, 0.0]);
+    assert_eq!(network.cast::<f64>().propagate(vec![0.0, 0.0]), &[0.0, 5.0]);
+
+    assert_eq!(
+        network.set_head(OutputHead::Clamped(vec![OutputRange::symmetric(1.0)])),
+        Err(NetworkError::ShapeMismatch { expected: 2, actual: 1 })
+    );
+    assert_eq!(network.head(), &OutputHead::Scaled(ranges));
+
+    network.set_head(OutputHead::Softmax).unwrap();
+    assert_eq!(network.propagate(vec![3.0, 3.0]), &[0.5, 0.5]);
+}
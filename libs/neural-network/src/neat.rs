@@ -0,0 +1,632 @@
+//! NEAT-style networks: instead of a fixed stack of layers, a genome lists
+//! nodes and connections, and evolution can grow new ones.
+//!
+//! Every connection carries an innovation number, handed out by `Innovations`
+//! so that the same structural mutation gets the same number in every genome;
+//! crossover then lines genomes up by these numbers.
+
+use crate::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How many random node pairs `add_connection()` tries before giving up.
+const ADD_CONNECTION_ATTEMPTS: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Input,
+    Hidden,
+    Output,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NodeGene {
+    pub id: usize,
+    pub kind: NodeKind,
+    pub bias: f32,
+    pub activation: Activation,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionGene {
+    pub innovation: usize,
+    pub from: usize,
+    pub to: usize,
+    pub weight: f32,
+    pub enabled: bool,
+}
+
+/// Graph-based network description; nodes are kept sorted by id and
+/// connections by innovation number.
+///
+/// Connections never form cycles, so every genome describes a feed-forward
+/// network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Genome {
+    nodes: Vec<NodeGene>,
+    connections: Vec<ConnectionGene>,
+}
+
+/// Registry of structural mutations seen so far.
+#[derive(Clone, Debug, Default)]
+pub struct Innovations {
+    connections: HashMap<(usize, usize), usize>,
+    /// Id of the node created by splitting connection with given innovation
+    splits: HashMap<usize, usize>,
+    next_node: usize,
+}
+
+impl Innovations {
+    /// Registry for genomes with given number of inputs and outputs, which
+    /// take node ids `0..inputs + outputs`.
+    pub fn new(inputs: usize, outputs: usize) -> Self {
+        Self {
+            next_node: inputs + outputs,
+            ..Self::default()
+        }
+    }
+
+    pub fn connection(&mut self, from: usize, to: usize) -> usize {
+        let next = self.connections.len();
+        *self.connections.entry((from, to)).or_insert(next)
+    }
+
+    fn split(&mut self, innovation: usize) -> usize {
+        let next_node = &mut self.next_node;
+
+        *self.splits.entry(innovation).or_insert_with(|| {
+            *next_node += 1;
+            *next_node - 1
+        })
+    }
+}
+
+impl Genome {
+    /// Genome with every input connected straight to every output, with
+    /// biases and weights drawn uniformly from -1..=1.
+    pub fn minimal(rng: &mut dyn RngCore, innovations: &mut Innovations, inputs: usize, outputs: usize, output_activation: Activation) -> Self {
+        let input_nodes = (0..inputs).map(|id| NodeGene {
+            id,
+            kind: NodeKind::Input,
+            bias: 0.0,
+            activation: Activation::Identity,
+        });
+        let output_nodes: Vec<_> = (inputs..inputs + outputs).map(|id| NodeGene {
+            id,
+            kind: NodeKind::Output,
+            bias: rng.gen_range(-1.0..=1.0),
+            activation: output_activation,
+        }).collect();
+
+        let mut connections = Vec::with_capacity(inputs * outputs);
+        for from in 0..inputs {
+            for to in inputs..inputs + outputs {
+                connections.push(ConnectionGene {
+                    innovation: innovations.connection(from, to),
+                    from,
+                    to,
+                    weight: rng.gen_range(-1.0..=1.0),
+                    enabled: true,
+                });
+            }
+        }
+        connections.sort_by_key(|connection| connection.innovation);
+
+        Self {
+            nodes: input_nodes.chain(output_nodes).collect(),
+            connections,
+        }
+    }
+
+    pub fn nodes(&self) -> &[NodeGene] {
+        &self.nodes
+    }
+
+    pub fn connections(&self) -> &[ConnectionGene] {
+        &self.connections
+    }
+
+    /// Nudges every bias and weight, each with probability `chance`, by a
+    /// value drawn from `N(0, power)`.
+    pub fn mutate_weights(&mut self, rng: &mut dyn RngCore, chance: f32, power: f32) {
+        let normal = Normal::new(0.0, power).expect("mutation power must be finite and non-negative");
+
+        let biases = self.nodes.iter_mut().filter(|node| node.kind != NodeKind::Input).map(|node| &mut node.bias);
+        let weights = self.connections.iter_mut().map(|connection| &mut connection.weight);
+
+        for value in biases.chain(weights) {
+            if rng.gen_bool(chance as _) {
+                *value += normal.sample(rng);
+            }
+        }
+    }
+
+    /// Connects two so far unconnected nodes, as long as that doesn't form a
+    /// cycle; returns whether a connection was added.
+    pub fn add_connection(&mut self, rng: &mut dyn RngCore, innovations: &mut Innovations) -> bool {
+        for _ in 0..ADD_CONNECTION_ATTEMPTS {
+            let from = self.nodes.choose(rng).expect("genome has no nodes");
+            let to = self.nodes.choose(rng).expect("genome has no nodes");
+
+            if from.kind == NodeKind::Output || to.kind == NodeKind::Input || from.id == to.id {
+                continue;
+            }
+
+            let (from, to) = (from.id, to.id);
+            if self.connections.iter().any(|c| c.from == from && c.to == to) || self.reaches(to, from) {
+                continue;
+            }
+
+            self.insert_connection(ConnectionGene {
+                innovation: innovations.connection(from, to),
+                from,
+                to,
+                weight: rng.gen_range(-1.0..=1.0),
+                enabled: true,
+            });
+            return true;
+        }
+
+        false
+    }
+
+    /// Splits a random enabled connection in two, with a new hidden node in
+    /// between; returns whether a node was added.
+    ///
+    /// The incoming connection gets a weight of 1.0 and the outgoing one
+    /// keeps the old weight, so the network initially behaves (almost) as
+    /// it did before.
+    pub fn add_node(&mut self, rng: &mut dyn RngCore, innovations: &mut Innovations, activation: Activation) -> bool {
+        let enabled: Vec<usize> = (0..self.connections.len()).filter(|&idx| self.connections[idx].enabled).collect();
+        let Some(&idx) = enabled.choose(rng) else {
+            return false;
+        };
+
+        let split = &self.connections[idx];
+        let node = innovations.split(split.innovation);
+        if self.node(node).is_some() {
+            return false;
+        }
+
+        let (from, to, weight) = (split.from, split.to, split.weight);
+        self.connections[idx].enabled = false;
+
+        let position = self.nodes.partition_point(|other| other.id < node);
+        self.nodes.insert(position, NodeGene {
+            id: node,
+            kind: NodeKind::Hidden,
+            bias: 0.0,
+            activation,
+        });
+
+        for (from, to, weight) in [(from, node, 1.0), (node, to, weight)] {
+            self.insert_connection(ConnectionGene {
+                innovation: innovations.connection(from, to),
+                from,
+                to,
+                weight,
+                enabled: true,
+            });
+        }
+
+        true
+    }
+
+    /// Creates a child that inherits the structure of `fitter`; genes
+    /// present in both parents are picked at random from either of them.
+    pub fn crossover(rng: &mut dyn RngCore, fitter: &Genome, other: &Genome) -> Genome {
+        let nodes = fitter.nodes.iter().map(|node| match other.node(node.id) {
+            Some(other) if rng.gen_bool(0.5) => NodeGene { bias: other.bias, ..node.clone() },
+            _ => node.clone(),
+        }).collect();
+
+        let connections = fitter.connections.iter().map(|connection| match other.connection(connection.innovation) {
+            Some(other) if rng.gen_bool(0.5) => ConnectionGene {
+                weight: other.weight,
+                ..connection.clone()
+            },
+            _ => connection.clone(),
+        }).collect();
+
+        Self { nodes, connections }
+    }
+
+    /// NEAT's compatibility distance: the more genes differ (`excess` ones
+    /// beyond the other genome's newest innovation, `disjoint` ones before
+    /// it) and the more weights of matching genes differ, the larger.
+    pub fn compatibility(&self, other: &Genome, excess: f32, disjoint: f32, weight: f32) -> f32 {
+        let newest = |genome: &Genome| genome.connections.last().map_or(0, |c| c.innovation);
+        let (self_newest, other_newest) = (newest(self), newest(other));
+
+        let mut excess_genes = 0;
+        let mut disjoint_genes = 0;
+        let mut weight_difference = 0.0;
+        let mut matching = 0;
+
+        for connection in &self.connections {
+            match other.connection(connection.innovation) {
+                Some(other) => {
+                    weight_difference += (connection.weight - other.weight).abs();
+                    matching += 1;
+                }
+                None if connection.innovation > other_newest => excess_genes += 1,
+                None => disjoint_genes += 1,
+            }
+        }
+
+        for connection in &other.connections {
+            if self.connection(connection.innovation).is_none() {
+                if connection.innovation > self_newest {
+                    excess_genes += 1;
+                } else {
+                    disjoint_genes += 1;
+                }
+            }
+        }
+
+        let genes = self.connections.len().max(other.connections.len()).max(1) as f32;
+        let weight_difference = if matching > 0 { weight_difference / matching as f32 } else { 0.0 };
+
+        excess * excess_genes as f32 / genes + disjoint * disjoint_genes as f32 / genes + weight * weight_difference
+    }
+
+    /// Compiles the genome into something that can be propagated.
+    pub fn network(&self) -> NeatNetwork {
+        NeatNetwork::new(self)
+    }
+
+    fn node(&self, id: usize) -> Option<&NodeGene> {
+        self.nodes.binary_search_by_key(&id, |node| node.id).ok().map(|idx| &self.nodes[idx])
+    }
+
+    fn connection(&self, innovation: usize) -> Option<&ConnectionGene> {
+        self.connections
+            .binary_search_by_key(&innovation, |connection| connection.innovation)
+            .ok()
+            .map(|idx| &self.connections[idx])
+    }
+
+    fn insert_connection(&mut self, connection: ConnectionGene) {
+        let position = self.connections.partition_point(|other| other.innovation < connection.innovation);
+        self.connections.insert(position, connection);
+    }
+
+    /// Whether there's a path of (enabled or not) connections from `from` to `to`.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut pending = vec![from];
+        let mut visited = vec![from];
+
+        while let Some(node) = pending.pop() {
+            if node == to {
+                return true;
+            }
+
+            for connection in self.connections.iter().filter(|c| c.from == node) {
+                if !visited.contains(&connection.to) {
+                    visited.push(connection.to);
+                    pending.push(connection.to);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Genome compiled for evaluation: nodes in topological order, each with
+/// the list of nodes it reads from.
+#[derive(Clone, Debug)]
+pub struct NeatNetwork {
+    inputs: usize,
+    /// Positions (within `nodes`) of the output nodes, by ascending id
+    outputs: Vec<usize>,
+    nodes: Vec<NeatNode>,
+}
+
+#[derive(Clone, Debug)]
+struct NeatNode {
+    bias: f32,
+    activation: Activation,
+    /// Positions (within `NeatNetwork::nodes`) of source nodes, with weights
+    incoming: Vec<(usize, f32)>,
+}
+
+impl NeatNetwork {
+    fn new(genome: &Genome) -> Self {
+        let enabled: Vec<&ConnectionGene> = genome.connections.iter().filter(|c| c.enabled).collect();
+
+        // Kahn's algorithm, with inputs (in id order) going first
+        let mut pending_inputs: HashMap<usize, usize> = genome.nodes.iter().map(|node| (node.id, 0)).collect();
+        for connection in &enabled {
+            *pending_inputs.get_mut(&connection.to).expect("connection to unknown node") += 1;
+        }
+
+        let mut order: Vec<usize> = genome.nodes.iter().filter(|node| node.kind == NodeKind::Input).map(|node| node.id).collect();
+        order.extend(genome.nodes.iter().filter(|node| node.kind != NodeKind::Input && pending_inputs[&node.id] == 0).map(|node| node.id));
+
+        let mut next = 0;
+        while next < order.len() {
+            let node = order[next];
+            next += 1;
+
+            for connection in enabled.iter().filter(|c| c.from == node) {
+                let pending = pending_inputs.get_mut(&connection.to).expect("connection to unknown node");
+                *pending -= 1;
+                if *pending == 0 {
+                    order.push(connection.to);
+                }
+            }
+        }
+
+        let positions: HashMap<usize, usize> = order.iter().enumerate().map(|(position, &id)| (id, position)).collect();
+
+        let nodes = order.iter().map(|&id| {
+            let gene = genome.node(id).expect("unknown node");
+            let incoming = enabled
+                .iter()
+                .filter(|c| c.to == id)
+                .map(|c| (positions[&c.from], c.weight))
+                .collect();
+
+            NeatNode { bias: gene.bias, activation: gene.activation, incoming }
+        }).collect();
+
+        let outputs = genome.nodes.iter().filter(|node| node.kind == NodeKind::Output).map(|node| positions[&node.id]).collect();
+
+        Self {
+            inputs: genome.nodes.iter().filter(|node| node.kind == NodeKind::Input).count(),
+            outputs,
+            nodes,
+        }
+    }
+
+    pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        assert_eq!(inputs.len(), self.inputs);
+
+        let mut values = vec![0.0; self.nodes.len()];
+        values[..self.inputs].copy_from_slice(inputs);
+
+        for (position, node) in self.nodes.iter().enumerate().skip(self.inputs) {
+            let sum: f32 = node.incoming.iter().map(|&(source, weight)| values[source] * weight).sum();
+            values[position] = node.activation.apply(sum + node.bias);
+        }
+
+        self.outputs.iter().map(|&position| values[position]).collect()
+    }
+}
+
+/// Knobs of `Neat::evolve()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NeatConfig {
+    /// Probability of nudging each bias and weight
+    pub weight_chance: f32,
+    /// Standard deviation of those nudges
+    pub weight_power: f32,
+    pub add_connection_chance: f32,
+    pub add_node_chance: f32,
+    pub hidden_activation: Activation,
+    pub output_activation: Activation,
+}
+
+impl Default for NeatConfig {
+    fn default() -> Self {
+        Self {
+            weight_chance: 0.1,
+            weight_power: 0.3,
+            add_connection_chance: 0.05,
+            add_node_chance: 0.03,
+            hidden_activation: Activation::Tanh,
+            output_activation: Activation::Tanh,
+        }
+    }
+}
+
+/// Population-level bookkeeping for evolving genomes of a fixed number of
+/// inputs and outputs.
+#[derive(Clone, Debug)]
+pub struct Neat {
+    inputs: usize,
+    outputs: usize,
+    config: NeatConfig,
+    innovations: Innovations,
+}
+
+impl Neat {
+    pub fn new(inputs: usize, outputs: usize, config: NeatConfig) -> Self {
+        Self {
+            inputs,
+            outputs,
+            config,
+            innovations: Innovations::new(inputs, outputs),
+        }
+    }
+
+    /// Fresh genome for the first generation.
+    pub fn genome(&mut self, rng: &mut dyn RngCore) -> Genome {
+        Genome::minimal(rng, &mut self.innovations, self.inputs, self.outputs, self.config.output_activation)
+    }
+
+    /// Breeds a generation of the same size out of `population`, i.e. pairs
+    /// of genomes and their fitness; parents are picked proportionally to
+    /// fitness (or uniformly, when nobody scored anything).
+    ///
+    /// Negative fitness counts as zero; NaN or infinite fitness is reported
+    /// as `NetworkError::BadFitness`.
+    pub fn evolve(&mut self, rng: &mut dyn RngCore, population: &[(Genome, f32)]) -> Result<Vec<Genome>, NetworkError> {
+        if let Some(genome) = population.iter().position(|(_, fitness)| !fitness.is_finite()) {
+            return Err(NetworkError::BadFitness(genome));
+        }
+
+        // summed as `f64`, so that neither huge nor tiny fitness overflows
+        // or rounds the total away
+        let weight = |(_, fitness): &(Genome, f32)| fitness.max(0.0) as f64;
+        let weighted = population.iter().map(weight).sum::<f64>() > 0.0;
+        let select = |rng: &mut dyn RngCore| {
+            if weighted {
+                population.choose_weighted(rng, weight).expect("weights are finite and not all zero")
+            } else {
+                population.choose(rng).expect("got an empty population")
+            }
+        };
+
+        Ok((0..population.len()).map(|_| {
+            let (a, a_fitness) = select(rng);
+            let (b, b_fitness) = select(rng);
+            let (fitter, other) = if a_fitness >= b_fitness { (a, b) } else { (b, a) };
+
+            let mut child = Genome::crossover(rng, fitter, other);
+            child.mutate_weights(rng, self.config.weight_chance, self.config.weight_power);
+
+            if rng.gen_bool(self.config.add_connection_chance as _) {
+                child.add_connection(rng, &mut self.innovations);
+            }
+            if rng.gen_bool(self.config.add_node_chance as _) {
+                child.add_node(rng, &mut self.innovations, self.config.hidden_activation);
+            }
+
+            child
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+fn test_rng() -> rand_chacha::ChaCha8Rng {
+    use rand::SeedableRng;
+
+    rand_chacha::ChaCha8Rng::from_seed(Default::default())
+}
+
+#[test]
+fn test_minimal_genome() {
+    let mut rng = test_rng();
+    let mut innovations = Innovations::new(2, 1);
+    let genome = Genome::minimal(&mut rng, &mut innovations, 2, 1, Activation::Identity);
+
+    assert_eq!(genome.nodes().len(), 3);
+    assert_eq!(genome.connections().iter().map(|c| (c.innovation, c.from, c.to)).collect::<Vec<_>>(), [(0, 0, 2), (1, 1, 2)]);
+
+    let [a, b] = [genome.connections()[0].weight, genome.connections()[1].weight];
+    let bias = genome.nodes()[2].bias;
+    approx::assert_relative_eq!(genome.network().propagate(&[0.5, 2.0])[0], 0.5 * a + 2.0 * b + bias);
+}
+
+#[test]
+fn test_add_node_preserves_behaviour() {
+    let mut rng = test_rng();
+    let mut innovations = Innovations::new(2, 1);
+    let mut genome = Genome::minimal(&mut rng, &mut innovations, 2, 1, Activation::Identity);
+    let before = genome.network().propagate(&[0.3, -0.6]);
+
+    assert!(genome.add_node(&mut rng, &mut innovations, Activation::Identity));
+
+    assert_eq!(genome.nodes().len(), 4);
+    assert_eq!(genome.nodes()[3].id, 3);
+    assert_eq!(genome.connections().iter().filter(|c| c.enabled).count(), 3);
+    approx::assert_relative_eq!(genome.network().propagate(&[0.3, -0.6])[0], before[0]);
+}
+
+#[test]
+fn test_innovations_are_shared() {
+    use rand::SeedableRng;
+
+    let mut innovations = Innovations::new(2, 1);
+    assert_eq!(innovations.connection(0, 2), 0);
+    assert_eq!(innovations.connection(1, 2), 1);
+    assert_eq!(innovations.connection(0, 2), 0);
+    assert_eq!(innovations.split(1), 3);
+    assert_eq!(innovations.split(1), 3);
+
+    // with a single connection to split, two genomes make the same split
+    // independently, whatever their rngs pick
+    let mut innovations = Innovations::new(1, 1);
+    let base = Genome::minimal(&mut test_rng(), &mut innovations, 1, 1, Activation::Tanh);
+    let mut a = base.clone();
+    let mut b = base.clone();
+    assert!(a.add_node(&mut test_rng(), &mut innovations, Activation::Tanh));
+    assert!(b.add_node(&mut rand_chacha::ChaCha8Rng::seed_from_u64(1), &mut innovations, Activation::Tanh));
+
+    let genes = |genome: &Genome| genome.connections().iter().map(|c| (c.innovation, c.from, c.to, c.enabled)).collect::<Vec<_>>();
+    assert_eq!(a.nodes()[2].id, 2);
+    assert_eq!(genes(&a), [(0, 0, 1, false), (1, 0, 2, true), (2, 2, 1, true)]);
+    assert_eq!(genes(&b), genes(&a));
+    assert_eq!(a, b);
+    assert_eq!(a.compatibility(&b, 1.0, 1.0, 0.4), 0.0);
+}
+
+#[test]
+fn test_add_connection_keeps_network_acyclic() {
+    let mut rng = test_rng();
+    let mut innovations = Innovations::new(3, 2);
+    let mut genome = Genome::minimal(&mut rng, &mut innovations, 3, 2, Activation::Tanh);
+
+    for _ in 0..30 {
+        genome.add_node(&mut rng, &mut innovations, Activation::Tanh);
+        genome.add_connection(&mut rng, &mut innovations);
+    }
+
+    for connection in genome.connections() {
+        assert!(!genome.reaches(connection.to, connection.from));
+    }
+    // every node got evaluated, i.e. the topological sort didn't stall
+    assert_eq!(genome.network().nodes.len(), genome.nodes().len());
+}
+
+#[test]
+fn test_crossover_follows_fitter_parent() {
+    let mut rng = test_rng();
+    let mut innovations = Innovations::new(2, 2);
+    let base = Genome::minimal(&mut rng, &mut innovations, 2, 2, Activation::Tanh);
+
+    let mut fitter = base.clone();
+    fitter.add_node(&mut rng, &mut innovations, Activation::Tanh);
+    let mut other = base.clone();
+    other.mutate_weights(&mut rng, 1.0, 1.0);
+    other.add_connection(&mut rng, &mut innovations);
+
+    let child = Genome::crossover(&mut rng, &fitter, &other);
+
+    assert_eq!(child.nodes().len(), fitter.nodes().len());
+    for (child, fitter) in child.connections().iter().zip(fitter.connections()) {
+        assert_eq!((child.innovation, child.enabled), (fitter.innovation, fitter.enabled));
+
+        let other = other.connection(child.innovation).map(|other| other.weight);
+        assert!(child.weight == fitter.weight || Some(child.weight) == other);
+    }
+    assert!(fitter.compatibility(&other, 1.0, 1.0, 0.4) > 0.0);
+}
+
+#[test]
+fn test_evolve() {
+    let mut rng = test_rng();
+    let mut neat = Neat::new(3, 2, NeatConfig { add_node_chance: 0.5, add_connection_chance: 0.5, ..NeatConfig::default() });
+    let mut population: Vec<Genome> = (0..10).map(|_| neat.genome(&mut rng)).collect();
+
+    for generation in 0..10 {
+        let scored: Vec<_> = population.into_iter().enumerate().map(|(idx, genome)| {
+            // nobody scores in the first generation
+            let fitness = if generation == 0 { 0.0 } else { idx as f32 };
+            (genome, fitness)
+        }).collect();
+
+        population = neat.evolve(&mut rng, &scored).unwrap();
+        assert_eq!(population.len(), 10);
+    }
+
+    assert!(population.iter().any(|genome| genome.nodes().len() > 5));
+    for genome in &population {
+        assert_eq!(genome.network().propagate(&[0.1, 0.2, 0.3]).len(), 2);
+    }
+
+    // even subnormal fitness gets parents picked by weight
+    let scored: Vec<_> = population.iter().cloned().zip([1e-45, 0.0, 2e-45].into_iter().cycle()).collect();
+    assert_eq!(neat.evolve(&mut rng, &scored).unwrap().len(), 10);
+
+    let mut scored: Vec<_> = population.into_iter().map(|genome| (genome, 1.0)).collect();
+    scored[3].1 = f32::NAN;
+    assert_eq!(neat.evolve(&mut rng, &scored).unwrap_err(), NetworkError::BadFitness(3));
+    scored[3].1 = f32::INFINITY;
+    assert_eq!(neat.evolve(&mut rng, &scored).unwrap_err(), NetworkError::BadFitness(3));
+}
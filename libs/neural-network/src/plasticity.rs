@@ -0,0 +1,204 @@
+//! Lifetime learning through the ABCD Hebbian rule: after every call, each
+//! connection's weight changes by
+//!
+//! ```text
+//! learning_rate * (a * pre * post + b * pre + c * post + d)
+//! ```
+//!
+//! where `pre` is the value the connection carried and `post` the activation
+//! of the neuron it leads to. Coefficients are evolved per connection, so
+//! evolution picks learning rules instead of (just) weights.
+
+use crate::*;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Learned weights are kept within `-WEIGHT_LIMIT..=WEIGHT_LIMIT`, since
+/// nothing else stops Hebbian learning from growing them without bound.
+const WEIGHT_LIMIT: f32 = 5.0;
+
+/// Range random rule coefficients are drawn from; rules are scaled by the
+/// learning rate anyway, so there's no point in starting them any wider.
+const RULE_COEFFICIENTS: RangeInclusive<f32> = -1.0..=1.0;
+
+/// Coefficients of the ABCD rule for a single connection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct HebbianRule {
+    /// Correlation of both ends
+    pub a: f32,
+    /// Presynaptic, i.e. connection's input only
+    pub b: f32,
+    /// Postsynaptic, i.e. neuron's output only
+    pub c: f32,
+    /// Constant drift
+    pub d: f32,
+}
+
+/// Dense network whose weights keep changing as it's used.
+///
+/// Genes are the network's starting weights (as `Network::weights()` lays
+/// them out), followed by a rule for every connection - layer by layer,
+/// neuron by neuron - of four genes each. Biases are not plastic, and
+/// layers can only read the layer right before them.
+#[derive(Clone, Debug)]
+pub struct PlasticNetwork {
+    network: Network,
+    /// Genes the network was born with, for `weights()` and `reset()`
+    initial: Vec<f32>,
+    rules: Vec<HebbianRule>,
+    learning_rate: f32,
+}
+
+impl HebbianRule {
+    pub fn apply(&self, pre: f32, post: f32) -> f32 {
+        self.a * pre * post + self.b * pre + self.c * post + self.d
+    }
+}
+
+impl PlasticNetwork {
+    pub fn random(rng: &mut dyn RngCore, layers: &[LayerTopology], learning_rate: f32) -> Self {
+        let network = Network::random(rng, layers);
+
+        let rules = (0..Self::connection_count(layers) * 4).map(|_| rng.gen_range(RULE_COEFFICIENTS));
+        let genes: Vec<f32> = network.weights().into_iter().chain(rules).collect();
+
+        Self::from_weights(layers, learning_rate, genes).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    pub fn from_weights(layers: &[LayerTopology], learning_rate: f32, weights: impl IntoIterator<Item = f32>) -> Result<Self, NetworkError> {
+        Network::validate(layers)?;
+        let unsupported = |layer: &LayerTopology| layer.kind != LayerKind::Dense || !layer.sources.is_previous();
+        if let Some(layer) = layers.iter().position(unsupported) {
+            return Err(NetworkError::UnsupportedLayer(layer));
+        }
+
+        let initial: Vec<f32> = weights.into_iter().collect();
+        let expected = Self::weight_count(layers);
+        if initial.len() != expected {
+            return Err(NetworkError::WrongGeneCount { expected, actual: initial.len() });
+        }
+
+        let (network_genes, rule_genes) = initial.split_at(Network::weight_count(layers));
+        let network = Network::try_from_weights(layers, network_genes.iter().copied())?;
+        let rules = rule_genes
+            .chunks_exact(4)
+            .map(|genes| HebbianRule { a: genes[0], b: genes[1], c: genes[2], d: genes[3] })
+            .collect();
+
+        Ok(Self { network, initial, rules, learning_rate })
+    }
+
+    /// Number of genes `from_weights()` expects for given topology.
+    pub fn weight_count(layers: &[LayerTopology]) -> usize {
+        Network::weight_count(layers) + 4 * Self::connection_count(layers)
+    }
+
+    /// Genes this network was born with; learning doesn't change them.
+    pub fn weights(&self) -> Vec<f32> {
+        self.initial.clone()
+    }
+
+    /// Network with the weights learned so far.
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn rules(&self) -> &[HebbianRule] {
+        &self.rules
+    }
+
+    /// Forgets everything learned since birth.
+    pub fn reset(&mut self) {
+        let layers = self.network.topology();
+        let genes = self.initial[..Network::weight_count(&layers)].iter().copied();
+
+        self.network = Network::from_weights(&layers, genes);
+    }
+
+    /// Responds to `inputs` and then updates the weights.
+    pub fn propagate(&mut self, inputs: Vec<f32>) -> Vec<f32> {
+        let mut trace = self.network.propagate_traced(inputs);
+        self.learn(&trace);
+
+        trace.layers.pop().map_or(trace.inputs, |layer| layer.activations)
+    }
+
+    fn learn(&mut self, trace: &Trace) {
+        let mut rules = self.rules.iter();
+
+        for (idx, layer) in self.network.layers.iter_mut().enumerate() {
+            let inputs = trace.activations(idx);
+            let outputs = trace.activations(idx + 1);
+
+            for (row, &post) in layer.weights.chunks_exact_mut(layer.input_size).zip(outputs) {
+                for ((weight, &pre), rule) in row.iter_mut().zip(inputs).zip(&mut rules) {
+                    let delta = self.learning_rate * rule.apply(pre, post);
+                    *weight = (*weight + delta).clamp(-WEIGHT_LIMIT, WEIGHT_LIMIT);
+                }
+            }
+        }
+    }
+
+    fn connection_count(layers: &[LayerTopology]) -> usize {
+        layers.windows(2).map(|layers| layers[0].neurons * layers[1].neurons).sum()
+    }
+}
+
+#[cfg(test)]
+fn plastic_network(rule: HebbianRule) -> PlasticNetwork {
+    let layers = Network::builder().input(1).layer(1, Activation::Identity).topology();
+
+    // bias 0, weight 0.5
+    PlasticNetwork::from_weights(&layers, 0.1, [0.0, 0.5, rule.a, rule.b, rule.c, rule.d]).unwrap()
+}
+
+#[test]
+fn test_rule() {
+    let rule = HebbianRule { a: 1.0, b: 2.0, c: 3.0, d: 4.0 };
+    assert_eq!(rule.apply(0.5, -1.0), -0.5 + 1.0 - 3.0 + 4.0);
+}
+
+#[test]
+fn test_learns() {
+    let mut network = plastic_network(HebbianRule { a: 1.0, ..Default::default() });
+
+    // weight 0.5 -> response 1.0 -> weight += 0.1 * 2 * 1
+    assert_eq!(network.propagate(vec![2.0]), &[1.0]);
+    approx::assert_relative_eq!(network.network().weights()[1], 0.7);
+    approx::assert_relative_eq!(network.propagate(vec![2.0])[0], 1.4);
+
+    // genes stay the same, learned weights are forgotten on reset
+    assert_eq!(network.weights(), &[0.0, 0.5, 1.0, 0.0, 0.0, 0.0]);
+    network.reset();
+    assert_eq!(network.network().weights(), &[0.0, 0.5]);
+}
+
+#[test]
+fn test_weights_are_bounded() {
+    let mut network = plastic_network(HebbianRule { d: 100.0, ..Default::default() });
+
+    network.propagate(vec![1.0]);
+    assert_eq!(network.network().weights()[1], WEIGHT_LIMIT);
+}
+
+#[test]
+fn test_genes() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let layers = Network::builder().input(3).layer(4, Activation::ReLU).layer(2, Activation::Tanh).topology();
+    let network = PlasticNetwork::random(&mut rng, &layers, 0.01);
+
+    assert_eq!(PlasticNetwork::weight_count(&layers), 26 + 4 * (12 + 8));
+    assert_eq!(network.rules().len(), 12 + 8);
+    assert!(network.rules().iter().all(|rule| [rule.a, rule.b, rule.c, rule.d].iter().all(|coefficient| RULE_COEFFICIENTS.contains(coefficient))));
+
+    let rebuilt = PlasticNetwork::from_weights(&layers, 0.01, network.weights()).unwrap();
+    assert_eq!(rebuilt.rules(), network.rules());
+    assert_eq!(rebuilt.network().weights(), network.network().weights());
+
+    let recurrent = Network::builder().input(1).layer_of_kind(1, Activation::Tanh, LayerKind::Elman).topology();
+    assert_eq!(
+        PlasticNetwork::from_weights(&recurrent, 0.01, []).unwrap_err(),
+        NetworkError::UnsupportedLayer(1)
+    );
+}
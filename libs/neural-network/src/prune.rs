@@ -0,0 +1,287 @@
+//! Simplifying evolved networks: connections whose weights are too small
+//! to matter are cut (i.e. zeroed), and hidden neurons that never fire -
+//! or whose every outgoing connection has been cut - are removed along with
+//! all their weights.
+//!
+//! A pruned network can be expanded back into its original topology, with
+//! removed neurons and cut connections coming back as zeros, so that it can
+//! be evolved further alongside unpruned ones.
+
+use crate::*;
+use std::fmt;
+
+/// Smaller network left after `Network::prune()`, along with what it takes
+/// to expand it back.
+#[derive(Clone, Debug)]
+pub struct Pruned {
+    pub network: Network,
+    pub report: PruneReport,
+    /// Topology of the original network
+    topology: Vec<LayerTopology>,
+    /// Which neurons of every original layer (inputs included) were kept
+    kept: Vec<Vec<bool>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PruneReport {
+    /// Non-zero weights that fell below the threshold
+    pub connections: usize,
+    /// Hidden neurons removed, as `(layer, neuron)` of the original network
+    /// with layers counted from the input layer as #0
+    pub neurons: Vec<(usize, usize)>,
+    /// Number of genes of the original network
+    pub weights_before: usize,
+    /// Number of genes of the pruned network
+    pub weights_after: usize,
+}
+
+impl Network {
+    /// Zeroes weights smaller than `threshold` (in absolute value) and drops
+    /// hidden neurons which output zero for every one of `probes`, or which
+    /// no remaining connection reads. Layers always keep at least one
+    /// neuron; without probes, only unread neurons are dropped.
+    ///
+    /// Recurrent and convolution layers are not supported.
+    pub fn prune<'a>(&self, threshold: f32, probes: impl IntoIterator<Item = &'a [f32]>) -> Result<Pruned, NetworkError> {
+        if let Some(layer) = self.layers.iter().position(|layer| layer.kind != LayerKind::Dense) {
+            return Err(NetworkError::UnsupportedLayer(layer + 1));
+        }
+
+        let topology = self.topology();
+        let mut report = PruneReport {
+            weights_before: Network::weight_count(&topology),
+            ..Default::default()
+        };
+
+        let mut kept = self.active_neurons(probes)?;
+
+        let mut layers = self.layers.clone();
+        for weight in layers.iter_mut().flat_map(|layer| &mut layer.weights) {
+            if *weight != 0.0 && weight.abs() < threshold {
+                *weight = 0.0;
+                report.connections += 1;
+            }
+        }
+
+        // going backwards, so that a neuron left unread by the removal of a
+        // later one gets removed too
+        for layer in (1..topology.len() - 1).rev() {
+            for neuron in 0..topology[layer].neurons {
+                kept[layer][neuron] &= Self::is_read(&topology, &layers, &kept, (layer, neuron));
+            }
+
+            if !kept[layer].contains(&true) {
+                kept[layer][0] = true;
+            }
+
+            report.neurons.extend((0..topology[layer].neurons).filter(|&neuron| !kept[layer][neuron]).map(|neuron| (layer, neuron)));
+        }
+        report.neurons.sort_unstable();
+
+        let layers = layers
+            .into_iter()
+            .enumerate()
+            .map(|(idx, layer)| {
+                let columns = columns(&topology, idx + 1);
+                let rows = layer.weights.chunks_exact(layer.input_size).zip(&layer.biases);
+
+                let mut weights = Vec::new();
+                let mut biases = Vec::new();
+                for ((row, &bias), _) in rows.zip(&kept[idx + 1]).filter(|(_, &kept)| kept) {
+                    biases.push(bias);
+                    weights.extend(row.iter().zip(&columns).filter(|(_, &(source, neuron))| kept[source][neuron]).map(|(&weight, _)| weight));
+                }
+
+                Layer {
+                    input_size: columns.iter().filter(|&&(source, neuron)| kept[source][neuron]).count(),
+                    weights,
+                    biases,
+                    ..layer
+                }
+            })
+            .collect();
+
+        let network = Network { layers, head: self.head.clone() };
+        report.weights_after = Network::weight_count(&network.topology());
+
+        Ok(Pruned { network, report, topology, kept })
+    }
+
+    /// Marks which neurons of every layer output anything but zero for at
+    /// least one of `probes`; inputs and outputs are always marked.
+    fn active_neurons<'a>(&self, probes: impl IntoIterator<Item = &'a [f32]>) -> Result<Vec<Vec<bool>>, NetworkError> {
+        let topology = self.topology();
+        let mut active: Vec<Vec<bool>> = topology.iter().map(|layer| vec![false; layer.neurons]).collect();
+        let mut probed = false;
+
+        for probe in probes {
+            if probe.len() != topology[0].neurons {
+                return Err(NetworkError::ShapeMismatch { expected: topology[0].neurons, actual: probe.len() });
+            }
+
+            let trace = self.propagate_traced(probe.to_vec());
+            for (layer, active) in active.iter_mut().enumerate().skip(1) {
+                for (active, &activation) in active.iter_mut().zip(trace.activations(layer)) {
+                    *active |= activation != 0.0;
+                }
+            }
+            probed = true;
+        }
+
+        let last = active.len() - 1;
+        for (layer, active) in active.iter_mut().enumerate() {
+            if !probed || layer == 0 || layer == last {
+                active.fill(true);
+            }
+        }
+
+        Ok(active)
+    }
+
+    /// Whether any kept neuron of a later layer reads `(layer, neuron)`
+    /// through a non-zero weight.
+    fn is_read(topology: &[LayerTopology], layers: &[Layer], kept: &[Vec<bool>], (layer, neuron): (usize, usize)) -> bool {
+        (layer + 1..topology.len()).any(|reader| {
+            let Some(column) = columns(topology, reader).iter().position(|&source| source == (layer, neuron)) else {
+                return false;
+            };
+            let weights = &layers[reader - 1];
+
+            weights
+                .weights
+                .chunks_exact(weights.input_size)
+                .zip(&kept[reader])
+                .any(|(row, &kept)| kept && row[column] != 0.0)
+        })
+    }
+}
+
+impl Pruned {
+    /// Expands `network` - the pruned network, possibly evolved since - back
+    /// into the original topology; removed neurons and the connections of
+    /// removed neurons come back with zero weights and biases. The head is
+    /// kept as it is.
+    pub fn expand(&self, network: &Network) -> Result<Network, NetworkError> {
+        if network.topology() != self.network.topology() {
+            return Err(NetworkError::TopologyMismatch);
+        }
+
+        let mut genes = Vec::with_capacity(self.report.weights_before);
+
+        for (idx, layer) in network.layers.iter().enumerate() {
+            let columns = columns(&self.topology, idx + 1);
+            let mut rows = layer.weights.chunks_exact(layer.input_size).zip(&layer.biases);
+
+            for &kept in &self.kept[idx + 1] {
+                if !kept {
+                    genes.extend(std::iter::repeat_n(0.0, 1 + columns.len()));
+                    continue;
+                }
+
+                let (row, &bias) = rows.next().expect("pruned network matches its topology");
+                genes.push(bias);
+                let mut row = row.iter();
+                for &(source, neuron) in &columns {
+                    genes.push(if self.kept[source][neuron] { *row.next().unwrap() } else { 0.0 });
+                }
+            }
+        }
+
+        let mut expanded = Network::try_from_weights(&self.topology, genes)?;
+        expanded.head = network.head.clone();
+        Ok(expanded)
+    }
+}
+
+/// Every input of layer #`layer` of given topology, as the `(layer, neuron)`
+/// it comes from.
+fn columns(topology: &[LayerTopology], layer: usize) -> Vec<(usize, usize)> {
+    topology[layer]
+        .sources
+        .resolve(layer)
+        .flat_map(|source| (0..topology[source].neurons).map(move |neuron| (source, neuron)))
+        .collect()
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cut {} connections and removed {} neurons, {} -> {} genes",
+            self.connections,
+            self.neurons.len(),
+            self.weights_before,
+            self.weights_after,
+        )
+    }
+}
+
+#[cfg(test)]
+fn prune_probes() -> Vec<Vec<f32>> {
+    vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.5, 0.5]]
+}
+
+#[test]
+fn test_prune() {
+    let network = Network::builder()
+        .input(2)
+        .layer(3, Activation::ReLU)
+        .layer(1, Activation::Identity)
+        .build_from_weights([
+            0.1, 0.5, 0.005,
+            // never fires for inputs within 0..=1
+            -10.0, 1.0, 1.0,
+            0.2, 1.0, 1.0,
+            // barely reads the third neuron
+            0.3, 2.0, 1.5, 0.001,
+        ])
+        .unwrap();
+    let probes = prune_probes();
+    let pruned = network.prune(0.01, probes.iter().map(Vec::as_slice)).unwrap();
+
+    assert_eq!(pruned.report.connections, 2);
+    assert_eq!(pruned.report.neurons, &[(1, 1), (1, 2)]);
+    assert_eq!(pruned.report.to_string(), "cut 2 connections and removed 2 neurons, 13 -> 5 genes");
+    assert_eq!(pruned.network.weights(), &[0.1, 0.5, 0.0, 0.3, 2.0]);
+
+    for probe in &probes {
+        let expected = network.propagate(probe.clone());
+        approx::assert_relative_eq!(pruned.network.propagate(probe.clone())[0], expected[0], epsilon = 0.02);
+    }
+
+    let expanded = pruned.expand(&pruned.network).unwrap();
+    assert_eq!(expanded.topology(), network.topology());
+    assert_eq!(
+        expanded.weights(),
+        &[0.1, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.3, 2.0, 0.0, 0.0]
+    );
+    assert_eq!(pruned.expand(&network).unwrap_err(), NetworkError::TopologyMismatch);
+}
+
+#[test]
+fn test_prune_keeps_every_layer() {
+    // the hidden layer never fires, but the inputs also skip straight to
+    // the output
+    let network = Network::builder()
+        .input(1)
+        .layer(2, Activation::ReLU)
+        .layer(1, Activation::Identity)
+        .sources([0, 1])
+        .build_from_weights([-5.0, 1.0, -5.0, 1.0, 0.0, 2.0, 1.0, 1.0])
+        .unwrap();
+    let pruned = network.prune(0.0, [[0.5].as_slice()]).unwrap();
+
+    assert_eq!(pruned.report.neurons, &[(1, 1)]);
+    assert_eq!(pruned.network.weights(), &[-5.0, 1.0, 0.0, 2.0, 1.0]);
+    assert_eq!(pruned.network.propagate(vec![0.5]), &[1.0]);
+    assert_eq!(
+        pruned.expand(&pruned.network).unwrap().weights(),
+        &[-5.0, 1.0, 0.0, 0.0, 0.0, 2.0, 1.0, 0.0]
+    );
+
+    // without probes, nothing counts as dead
+    assert!(network.prune(0.0, []).unwrap().report.neurons.is_empty());
+
+    let recurrent = Network::builder().input(1).layer_of_kind(1, Activation::Tanh, LayerKind::Elman).build_from_weights([0.0; 3]).unwrap();
+    assert_eq!(recurrent.prune(0.1, []).unwrap_err(), NetworkError::UnsupportedLayer(1));
+}
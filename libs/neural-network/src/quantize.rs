@@ -0,0 +1,252 @@
+use crate::*;
+
+/// Largest magnitude of a quantized value; -128 is left out so that the
+/// range stays symmetric.
+const QMAX: f32 = i8::MAX as f32;
+
+/// Largest magnitude of a quantized bias, leaving the rest of `i32` to the
+/// products of weights and inputs.
+const BIAS_LIMIT: f32 = (1 << 30) as f32;
+
+/// Dense network with its weights stored as `i8`, for populations too large
+/// to keep in full precision.
+///
+/// Each layer has one scale factor for its weights, fixed when quantizing;
+/// the values entering a layer get quantized on the fly with their own
+/// scale, and the biases into the scale of their products, so the weighted
+/// sums are computed entirely in `i8 x i8 -> i32` arithmetic and only
+/// converted back to `f32` to apply activation.
+///
+/// Weights take a quarter of the memory, but evaluation isn't any faster:
+/// quantizing the inputs and biases of every layer on every call costs as
+/// much as the integer dot products save - `propagate_into()` takes about
+/// as long as `Network::propagate_into()` for layers of a hundred or so
+/// neurons, and about twice as long for small ones like the simulation's.
+#[derive(Clone, Debug)]
+pub struct QuantizedNetwork {
+    layers: Vec<QuantizedLayer>,
+    head: OutputHead,
+}
+
+#[derive(Clone, Debug)]
+struct QuantizedLayer {
+    input_size: usize,
+    /// Row-major `output_size x input_size`, like `Layer::weights`
+    weights: Vec<i8>,
+    /// Value of a single step of `weights`
+    scale: f32,
+    biases: Vec<f32>,
+    /// Smallest step inputs get quantized with, so that quantized biases
+    /// stay within `BIAS_LIMIT`
+    min_input_scale: f32,
+    activation: Activation,
+}
+
+/// Reusable buffers for `QuantizedNetwork::propagate_into()`.
+#[derive(Clone, Debug, Default)]
+pub struct QuantizedScratch {
+    /// Output of the layer evaluated last
+    outputs: Vec<f32>,
+    /// Output of the layer being evaluated
+    next: Vec<f32>,
+    /// Quantized inputs of the layer being evaluated
+    inputs: Vec<i8>,
+    /// Biases of the layer being evaluated, in the scale of its sums
+    biases: Vec<i32>,
+}
+
+/// How far a quantized network strays from its original.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Deviation {
+    /// Largest absolute difference of any output
+    pub max: f32,
+    /// Mean absolute difference over all outputs
+    pub mean: f32,
+}
+
+impl QuantizedNetwork {
+    /// Quantizes `network`, which must consist of dense layers reading just
+    /// the layer before them.
+    pub fn new(network: &Network) -> Result<Self, NetworkError> {
+        let unsupported = |layer: &Layer| layer.kind != LayerKind::Dense || !layer.sources.is_previous();
+        if let Some(layer) = network.layers.iter().position(unsupported) {
+            return Err(NetworkError::UnsupportedLayer(layer + 1));
+        }
+
+        let layers = network
+            .layers
+            .iter()
+            .map(|layer| {
+                let mut weights = Vec::new();
+                let scale = quantize_into(&layer.weights, 0.0, &mut weights);
+                let largest_bias = layer.biases.iter().map(|bias| bias.abs()).fold(0.0, f32::max);
+
+                QuantizedLayer {
+                    input_size: layer.input_size,
+                    weights,
+                    scale,
+                    biases: layer.biases.clone(),
+                    min_input_scale: largest_bias / (scale * BIAS_LIMIT),
+                    activation: layer.activation,
+                }
+            })
+            .collect();
+
+        Ok(Self { layers, head: network.head.clone() })
+    }
+
+    pub fn propagate(&self, inputs: &[f32]) -> Vec<f32> {
+        let mut scratch = QuantizedScratch::default();
+        self.propagate_into(inputs, &mut scratch).to_vec()
+    }
+
+    /// Like `propagate()`, but reuses `scratch` instead of allocating on
+    /// every call.
+    pub fn propagate_into<'a>(&self, inputs: &[f32], scratch: &'a mut QuantizedScratch) -> &'a [f32] {
+        assert_eq!(inputs.len(), self.layers[0].input_size);
+
+        scratch.outputs.clear();
+        scratch.outputs.extend_from_slice(inputs);
+
+        for layer in &self.layers {
+            layer.propagate_into(scratch);
+            std::mem::swap(&mut scratch.outputs, &mut scratch.next);
+        }
+
+        self.head.apply(&mut scratch.outputs);
+        &scratch.outputs
+    }
+
+    /// Runs both networks over every one of `inputs` and compares their
+    /// outputs.
+    pub fn deviation<'a>(&self, original: &Network, inputs: impl IntoIterator<Item = &'a [f32]>) -> Deviation {
+        let mut deviation = Deviation::default();
+        let mut count = 0;
+
+        for inputs in inputs {
+            let expected = original.propagate(inputs.to_vec());
+            let actual = self.propagate(inputs);
+
+            for (expected, actual) in expected.iter().zip(&actual) {
+                let diff = (expected - actual).abs();
+
+                deviation.max = deviation.max.max(diff);
+                deviation.mean += diff;
+                count += 1;
+            }
+        }
+
+        if count > 0 {
+            deviation.mean /= count as f32;
+        }
+
+        deviation
+    }
+}
+
+impl QuantizedLayer {
+    /// Reads `scratch.outputs` and writes `scratch.next`.
+    fn propagate_into(&self, scratch: &mut QuantizedScratch) {
+        let input_scale = quantize_into(&scratch.outputs, self.min_input_scale, &mut scratch.inputs);
+        let scale = self.scale * input_scale;
+
+        scratch.biases.clear();
+        scratch.biases.extend(self.biases.iter().map(|bias| (bias / scale).round() as i32));
+
+        scratch.next.clear();
+        scratch.next.extend(self.weights.chunks_exact(self.input_size).zip(&scratch.biases).map(|(row, &bias)| {
+            let sum: i32 = row.iter().zip(&scratch.inputs).map(|(&weight, &input)| weight as i32 * input as i32).sum();
+            self.activation.apply((sum + bias) as f32 * scale)
+        }));
+    }
+}
+
+/// Maps `values` linearly onto `-127..=127`, with a step of at least
+/// `min_scale`, into `quantized`; returns the value of a single step.
+fn quantize_into(values: &[f32], min_scale: f32, quantized: &mut Vec<i8>) -> f32 {
+    let largest = values.iter().map(|value| value.abs()).fold(0.0, f32::max);
+    let scale = (largest / QMAX).max(min_scale);
+
+    quantized.clear();
+    if scale == 0.0 {
+        quantized.resize(values.len(), 0);
+        return 1.0;
+    }
+
+    quantized.extend(values.iter().map(|value| (value / scale).round().clamp(-QMAX, QMAX) as i8));
+    scale
+}
+
+#[test]
+fn test_quantize() {
+    let mut values = Vec::new();
+
+    approx::assert_relative_eq!(quantize_into(&[0.5, -1.27, 0.0, 0.004], 0.0, &mut values), 0.01);
+    assert_eq!(values, &[50, -127, 0, 0]);
+
+    assert_eq!(quantize_into(&[0.0, 0.0], 0.0, &mut values), 1.0);
+    assert_eq!(values, &[0, 0]);
+
+    // tiny inputs are kept from blowing the biases out of `i32`
+    approx::assert_relative_eq!(quantize_into(&[0.5, -1.27], 0.1, &mut values), 0.1);
+    assert_eq!(values, &[5, -13]);
+}
+
+#[test]
+fn test_close_to_original() {
+    let mut rng = ChaCha8Rng::from_seed(Default::default());
+    let network = Network::builder()
+        .input(9)
+        .layer(18, Activation::ReLU)
+        .layer(2, Activation::Tanh)
+        .build_random(&mut rng)
+        .unwrap();
+    let quantized = QuantizedNetwork::new(&network).unwrap();
+
+    let inputs: Vec<Vec<f32>> = (0..50)
+        .map(|_| (0..9).map(|_| rand::Rng::gen_range(&mut rng, 0.0..1.0)).collect())
+        .collect();
+    let deviation = quantized.deviation(&network, inputs.iter().map(Vec::as_slice));
+
+    assert!(deviation.max < 0.05, "{deviation:?}");
+    assert!(deviation.mean <= deviation.max);
+    assert!(deviation.mean > 0.0);
+}
+
+#[test]
+fn test_propagate_into_reuses_scratch() {
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::Identity)
+        .build_from_weights([0.5, 1.0, 0.0, -0.25, 0.0, -1.0])
+        .unwrap();
+    let quantized = QuantizedNetwork::new(&network).unwrap();
+    let mut scratch = QuantizedScratch::default();
+
+    let outputs = quantized.propagate_into(&[1.0, 0.5], &mut scratch).to_vec();
+    approx::assert_relative_eq!(outputs.as_slice(), [1.5, -0.75].as_slice(), epsilon = 0.01);
+    approx::assert_relative_eq!(quantized.propagate_into(&[0.0, 0.0], &mut scratch), [0.5, -0.25].as_slice(), epsilon = 0.01);
+    assert_eq!(quantized.propagate(&[1.0, 0.5]), outputs);
+}
+
+#[test]
+fn test_rejects_recurrent_layers() {
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::ReLU)
+        .layer_of_kind(1, Activation::Tanh, LayerKind::Jordan)
+        .build_from_weights([0.0; 10])
+        .unwrap();
+
+    assert_eq!(QuantizedNetwork::new(&network).unwrap_err(), NetworkError::UnsupportedLayer(2));
+
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::ReLU)
+        .layer(1, Activation::Tanh)
+        .sources([0, 1])
+        .build_from_weights([0.0; 11])
+        .unwrap();
+
+    assert_eq!(QuantizedNetwork::new(&network).unwrap_err(), NetworkError::UnsupportedLayer(2));
+}
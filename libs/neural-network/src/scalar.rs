@@ -0,0 +1,344 @@
+//! Numbers networks can compute with.
+//!
+//! `f32` and `f64` are fast, but functions such as `exp()` and `tanh()` come
+//! from the platform's math library and may differ in the last bits between
+//! machines, so a long seeded run can drift apart. `Fixed` trades speed and
+//! range for arithmetic that's bit-exact everywhere.
+//!
+//! Networks of any scalar can be propagated, traced and trained (see
+//! `Trainer`); building and the rest of the tooling work in `f32`, with
+//! `Network::cast()` converting between them.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+pub trait Scalar:
+    Copy
+    + fmt::Debug
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + Sum
+    + Send
+    + Sync
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+
+    fn abs(self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn sqrt(self) -> Self;
+    fn exp(self) -> Self;
+    fn tanh(self) -> Self;
+}
+
+impl Scalar for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn abs(self) -> Self {
+        self.abs()
+    }
+
+    fn max(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    fn sqrt(self) -> Self {
+        self.sqrt()
+    }
+
+    fn exp(self) -> Self {
+        self.exp()
+    }
+
+    fn tanh(self) -> Self {
+        self.tanh()
+    }
+}
+
+impl Scalar for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn abs(self) -> Self {
+        self.abs()
+    }
+
+    fn max(self, other: Self) -> Self {
+        self.max(other)
+    }
+
+    fn sqrt(self) -> Self {
+        self.sqrt()
+    }
+
+    fn exp(self) -> Self {
+        self.exp()
+    }
+
+    fn tanh(self) -> Self {
+        self.tanh()
+    }
+}
+
+/// Number of fractional bits of `Fixed`.
+const FRAC_BITS: u32 = 16;
+
+/// `ln(2)` with 32 fractional bits.
+const LN_2: i64 = 2_977_044_472;
+
+/// Number of terms of the Taylor series `Fixed::exp()` sums.
+const EXP_TERMS: u64 = 12;
+
+/// Past this, `tanh()` is within a step of ±1.
+const TANH_LIMIT: Fixed = Fixed(8 << FRAC_BITS);
+
+/// Signed Q16.16 fixed-point number, covering about ±32768 in steps of
+/// 1/65536; every operation saturates instead of overflowing.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const MIN: Self = Self(i32::MIN);
+    pub const MAX: Self = Self(i32::MAX);
+
+    pub const fn from_bits(bits: i32) -> Self {
+        Self(bits)
+    }
+
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    fn saturate(value: i64) -> Self {
+        Self(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+}
+
+impl Scalar for Fixed {
+    const ZERO: Self = Self(0);
+    const ONE: Self = Self(1 << FRAC_BITS);
+
+    /// Rounds to the nearest step.
+    fn from_f64(value: f64) -> Self {
+        // `as` saturates, and maps NaN to zero
+        Self((value * (1 << FRAC_BITS) as f64).round() as i32)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.0 as f64 / (1 << FRAC_BITS) as f64
+    }
+
+    fn abs(self) -> Self {
+        Self(self.0.saturating_abs())
+    }
+
+    fn max(self, other: Self) -> Self {
+        Ord::max(self, other)
+    }
+
+    /// Rounds down; negative numbers give zero.
+    fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        Self(((self.0 as u64) << FRAC_BITS).isqrt() as i32)
+    }
+
+    /// Computes `e^x` as `2^k * e^r`, with `0 <= r < ln(2)` and `e^r` summed
+    /// from its Taylor series - all in integers.
+    fn exp(self) -> Self {
+        let x = (self.0 as i64) << (32 - FRAC_BITS);
+        let k = x.div_euclid(LN_2);
+
+        if k >= 15 {
+            return Self::MAX;
+        }
+        if k < -(FRAC_BITS as i64) - 2 {
+            return Self::ZERO;
+        }
+
+        // both with 32 fractional bits; `e^r` is below 2
+        let r = (x - k * LN_2) as u64;
+        let mut term: u64 = 1 << 32;
+        let mut sum = term;
+        for n in 1..=EXP_TERMS {
+            term = ((term as u128 * r as u128) >> 32) as u64 / n;
+            sum += term;
+        }
+
+        let shift = (32 - FRAC_BITS as i64 - k) as u32;
+        let rounded = (sum + (1 << (shift - 1))) >> shift;
+        Self::saturate(rounded as i64)
+    }
+
+    fn tanh(self) -> Self {
+        let x = self.abs();
+        let y = if x >= TANH_LIMIT {
+            Self::ONE
+        } else {
+            let e = (x + x).exp();
+            (e - Self::ONE) / (e + Self::ONE)
+        };
+
+        if self.0 < 0 {
+            -y
+        } else {
+            y
+        }
+    }
+}
+
+impl Add for Fixed {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Self;
+
+    /// Rounds to the nearest step.
+    fn mul(self, rhs: Self) -> Self {
+        let product = self.0 as i64 * rhs.0 as i64;
+        Self::saturate((product + (1 << (FRAC_BITS - 1))) >> FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Self;
+
+    /// Truncates towards zero; dividing by zero saturates (or gives zero
+    /// for `0 / 0`).
+    fn div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            return match self.0.signum() {
+                1 => Self::MAX,
+                -1 => Self::MIN,
+                _ => Self::ZERO,
+            };
+        }
+
+        Self::saturate(((self.0 as i64) << FRAC_BITS) / rhs.0 as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sum for Fixed {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+#[cfg(test)]
+fn fixed(value: f64) -> Fixed {
+    Fixed::from_f64(value)
+}
+
+#[test]
+fn test_fixed_arithmetic() {
+    assert_eq!(fixed(1.5).to_bits(), 0x18000);
+    assert_eq!(fixed(-0.25).to_f64(), -0.25);
+
+    assert_eq!(fixed(1.5) + fixed(2.25), fixed(3.75));
+    assert_eq!(fixed(1.5) - fixed(2.25), fixed(-0.75));
+    assert_eq!(fixed(1.5) * fixed(-2.25), fixed(-3.375));
+    assert_eq!(fixed(-3.375) / fixed(1.5), fixed(-2.25));
+    assert_eq!(-fixed(1.5), fixed(-1.5));
+    assert_eq!([fixed(0.5), fixed(0.25), fixed(1.0)].into_iter().sum::<Fixed>(), fixed(1.75));
+
+    // saturates instead of wrapping around
+    assert_eq!(Fixed::MAX + Fixed::ONE, Fixed::MAX);
+    assert_eq!(fixed(300.0) * fixed(300.0), Fixed::MAX);
+    assert_eq!(fixed(-300.0) * fixed(300.0), Fixed::MIN);
+    assert_eq!(Fixed::ONE / Fixed::ZERO, Fixed::MAX);
+    assert_eq!(Fixed::ZERO / Fixed::ZERO, Fixed::ZERO);
+    assert_eq!(fixed(1e9), Fixed::MAX);
+}
+
+#[test]
+fn test_fixed_functions() {
+    for step in -120..=100 {
+        let x = step as f64 / 10.0;
+
+        let exp = fixed(x).exp().to_f64();
+        approx::assert_relative_eq!(exp, x.exp(), epsilon = 1e-4, max_relative = 1e-4);
+
+        let tanh = fixed(x).tanh().to_f64();
+        approx::assert_relative_eq!(tanh, x.tanh(), epsilon = 1e-4);
+    }
+
+    assert_eq!(fixed(2.25).sqrt(), fixed(1.5));
+    assert_eq!(fixed(-1.0).sqrt(), Fixed::ZERO);
+    approx::assert_relative_eq!(Fixed::MAX.sqrt().to_f64(), Fixed::MAX.to_f64().sqrt(), epsilon = 1e-4);
+
+    assert_eq!(Fixed::ZERO.exp(), Fixed::ONE);
+    assert_eq!(fixed(11.0).exp(), Fixed::MAX);
+    assert_eq!(fixed(-20.0).exp(), Fixed::ZERO);
+    assert_eq!(fixed(-1.3).tanh(), -fixed(1.3).tanh());
+}
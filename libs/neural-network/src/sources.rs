@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Earlier layers a layer reads, counting the input layer as #0 - e.g. to
+/// let raw inputs skip straight to the output layer.
+///
+/// Their outputs are concatenated in ascending order of the layers, which
+/// is also the order of every neuron's weights. Empty sources (the
+/// default) stand for just the layer right before, as in a plain chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "Vec<usize>", try_from = "Vec<usize>")]
+pub struct Sources(u64);
+
+impl Sources {
+    /// Layers #`LIMIT` and beyond can't be read from other layers.
+    pub const LIMIT: usize = u64::BITS as usize;
+
+    /// Panics if any of `layers` is not below `LIMIT`.
+    pub fn new(layers: impl IntoIterator<Item = usize>) -> Self {
+        layers.into_iter().fold(Self(0), |sources, layer| {
+            assert!(layer < Self::LIMIT, "layer #{layer} can't be a source");
+            Self(sources.0 | 1 << layer)
+        })
+    }
+
+    /// Whether these are the default sources, i.e. the previous layer only.
+    pub fn is_previous(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, layer: usize) -> bool {
+        layer < Self::LIMIT && self.0 & 1 << layer != 0
+    }
+
+    /// Layers listed explicitly, in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..Self::LIMIT).filter(move |&layer| self.contains(layer))
+    }
+
+    /// Layers actually read by layer #`layer`, in ascending order.
+    pub fn resolve(self, layer: usize) -> impl Iterator<Item = usize> {
+        let previous = (self.is_previous() && layer > 0).then(|| layer - 1);
+        previous.into_iter().chain(self.iter())
+    }
+}
+
+impl From<Sources> for Vec<usize> {
+    fn from(sources: Sources) -> Self {
+        sources.iter().collect()
+    }
+}
+
+impl TryFrom<Vec<usize>> for Sources {
+    type Error = String;
+
+    fn try_from(layers: Vec<usize>) -> Result<Self, Self::Error> {
+        match layers.iter().find(|&&layer| layer >= Self::LIMIT) {
+            Some(layer) => Err(format!("layer #{layer} can't be a source")),
+            None => Ok(Self::new(layers)),
+        }
+    }
+}
+
+impl fmt::Display for Sources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_previous() {
+            return write!(f, "previous");
+        }
+
+        for (idx, layer) in self.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "#{layer}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_sources() {
+    let sources = Sources::new([3, 0, 3]);
+
+    assert_eq!(sources.iter().collect::<Vec<_>>(), &[0, 3]);
+    assert_eq!(sources.resolve(5).collect::<Vec<_>>(), &[0, 3]);
+    assert!(sources.contains(3) && !sources.contains(1) && !sources.contains(100));
+    assert_eq!(sources.to_string(), "#0, #3");
+
+    let previous = Sources::default();
+    assert!(previous.is_previous());
+    assert_eq!(previous.resolve(5).collect::<Vec<_>>(), &[4]);
+    assert_eq!(previous.resolve(0).count(), 0);
+
+    assert_eq!(serde_json::to_string(&sources).unwrap(), "[0,3]");
+    assert_eq!(serde_json::from_str::<Sources>("[3,0]").unwrap(), sources);
+    assert!(serde_json::from_str::<Sources>("[64]").is_err());
+}
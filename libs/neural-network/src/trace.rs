@@ -0,0 +1,118 @@
+use crate::*;
+
+/// Everything a network computed during a single `propagate_traced()` call.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace<T = f32> {
+    pub inputs: Vec<T>,
+    /// One entry per layer, not counting the input layer
+    pub layers: Vec<LayerTrace<T>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayerTrace<T = f32> {
+    /// Weighted sums (bias included) before the activation function
+    pub sums: Vec<T>,
+    pub activations: Vec<T>,
+}
+
+impl<T: Scalar> Trace<T> {
+    /// The last layer's activations, before the network's output head -
+    /// which `propagate()` applies on top of them.
+    pub fn output(&self) -> &[T] {
+        self.layers.last().map_or(&self.inputs, |layer| &layer.activations)
+    }
+
+    /// Output of layer #`layer`, counting the input layer as #0.
+    pub fn activations(&self, layer: usize) -> &[T] {
+        match layer {
+            0 => &self.inputs,
+            _ => &self.layers[layer - 1].activations,
+        }
+    }
+
+    /// What `sources` (see `Network::sources()`) fed to a layer.
+    pub(crate) fn gather(&self, sources: impl Iterator<Item = usize>) -> Vec<T> {
+        sources.flat_map(|layer| self.activations(layer)).copied().collect()
+    }
+}
+
+impl<T: Scalar> Network<T> {
+    /// Like `propagate()`, but records every layer's sums and activations,
+    /// e.g. to visualize which neurons are firing; recurrent layers start
+    /// from a blank context, as they do there.
+    pub fn propagate_traced(&self, inputs: Vec<T>) -> Trace<T> {
+        assert_eq!(inputs.len(), self.layers[0].input_size);
+
+        let mut trace = Trace {
+            inputs,
+            layers: Vec::with_capacity(self.layers.len()),
+        };
+
+        for idx in 0..self.layers.len() {
+            let inputs = trace.gather(self.sources(idx));
+            let layer = &self.layers[idx];
+
+            let mut sums = vec![T::ZERO; layer.output_size()];
+            layer.sums_into(&inputs, &[], &mut sums);
+
+            let activations: Vec<T> = sums.iter().map(|&sum| layer.activation.apply(sum)).collect();
+            trace.layers.push(LayerTrace { sums, activations });
+        }
+
+        trace
+    }
+}
+
+#[test]
+fn test_trace_matches_propagate() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let network = Network::builder()
+        .input(3)
+        .layer(4, Activation::ReLU)
+        .layer_of_kind(2, Activation::Tanh, LayerKind::Elman)
+        .build_random(&mut rng)
+        .unwrap();
+
+    for inputs in [[0.5, -0.2, 0.9], [0.0, 1.0, -1.0]] {
+        let trace = network.propagate_traced(inputs.to_vec());
+
+        assert_eq!(trace.layers.len(), 2);
+        assert_eq!(trace.layers[1].sums.len(), 2);
+        assert_eq!(trace.output(), network.propagate(inputs.to_vec()));
+    }
+}
+
+#[test]
+fn test_trace_reveals_dead_relu_neurons() {
+    // second neuron's bias is so negative it can never fire for inputs within 0..=1
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::ReLU)
+        .build_from_weights([0.1, 0.5, 0.5, -10.0, 1.0, 1.0])
+        .unwrap();
+
+    for inputs in [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0]] {
+        let trace = network.propagate_traced(inputs.to_vec());
+        let hidden = &trace.layers[0];
+
+        assert!(hidden.activations[0] > 0.0);
+        assert!(hidden.sums[1] < 0.0);
+        assert_eq!(hidden.activations[1], 0.0);
+    }
+}
+
+#[test]
+fn test_trace_skips_the_head() {
+    let network = Network::builder()
+        .input(2)
+        .layer(2, Activation::Identity)
+        .head(OutputHead::Clamped(vec![OutputRange::symmetric(1.0); 2]))
+        .build_from_weights([0.0, 2.0, 0.0, 0.0, 0.0, -2.0])
+        .unwrap();
+
+    let trace = network.propagate_traced(vec![1.0, 1.0]);
+    assert_eq!(trace.output(), &[2.0, -2.0]);
+    assert_eq!(network.propagate(vec![1.0, 1.0]), &[1.0, -1.0]);
+}
@@ -0,0 +1,454 @@
+use crate::*;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+/// One labelled example: what the network sees and what it should respond.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample<T = f32> {
+    pub inputs: Vec<T>,
+    pub targets: Vec<T>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Optimizer {
+    /// Plain stochastic gradient descent
+    Sgd { learning_rate: f32 },
+    Adam { learning_rate: f32, beta1: f32, beta2: f32, epsilon: f32 },
+}
+
+impl Optimizer {
+    /// Adam with the hyperparameters suggested by its authors.
+    pub fn adam(learning_rate: f32) -> Self {
+        Self::Adam { learning_rate, beta1: 0.9, beta2: 0.999, epsilon: 1e-8 }
+    }
+}
+
+/// Supervised training of dense networks through backpropagation, using
+/// mean squared error as the loss.
+///
+/// Targets are compared against the last layer's activations, before the
+/// network's output head (if any).
+///
+/// Adam's state follows the network being trained; it starts over whenever
+/// the trainer gets a network of another topology.
+///
+/// Networks of any `Scalar` can be trained, computing in that scalar
+/// throughout, so seeded runs over `Fixed` networks end up with the very
+/// same weights on every machine. Adam doesn't go well with `Fixed` though:
+/// its squared gradients and `epsilon` mostly round down to zero, so train
+/// those with `Optimizer::Sgd` instead.
+#[derive(Clone, Debug)]
+pub struct Trainer<T = f32> {
+    optimizer: Optimizer,
+    batch_size: usize,
+    moments: Option<Moments<T>>,
+}
+
+/// Adam's running averages of the gradients and squared gradients.
+#[derive(Clone, Debug)]
+struct Moments<T> {
+    /// Topology of the network the averages were taken over
+    topology: Vec<LayerTopology>,
+    m: Gradients<T>,
+    v: Gradients<T>,
+    /// `beta1` and `beta2` to the power of the number of steps taken, kept
+    /// as running products rather than `powi()`-ed, which isn't bit-exact
+    beta1_power: T,
+    beta2_power: T,
+}
+
+/// Derivatives of the loss, laid out just like the layers' parameters.
+#[derive(Clone, Debug)]
+struct Gradients<T> {
+    layers: Vec<LayerGradients<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct LayerGradients<T> {
+    weights: Vec<T>,
+    biases: Vec<T>,
+}
+
+impl<T: Scalar> Trainer<T> {
+    pub fn new(optimizer: Optimizer, batch_size: usize) -> Self {
+        assert!(batch_size > 0);
+
+        Self { optimizer, batch_size, moments: None }
+    }
+
+    /// Runs one pass over `samples` in random order, updating `network`
+    /// after every mini-batch; returns the mean loss seen during the pass.
+    pub fn train_epoch(&mut self, rng: &mut dyn RngCore, network: &mut Network<T>, samples: &[Sample<T>]) -> Result<T, NetworkError> {
+        Self::check(network, samples)?;
+
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        order.shuffle(rng);
+
+        let mut total_loss = T::ZERO;
+        for batch in order.chunks(self.batch_size) {
+            let mut gradients = Gradients::zeros(network);
+
+            for &idx in batch {
+                total_loss += gradients.accumulate(network, &samples[idx]);
+            }
+
+            gradients.scale(T::ONE / T::from_f64(batch.len() as f64));
+            self.step(network, &gradients);
+        }
+
+        Ok(total_loss / T::from_f64(samples.len().max(1) as f64))
+    }
+
+    /// Mean squared error of `network` over `samples`.
+    pub fn loss(network: &Network<T>, samples: &[Sample<T>]) -> Result<T, NetworkError> {
+        Self::check(network, samples)?;
+
+        let total: T = samples
+            .iter()
+            .map(|sample| mse(network.propagate_traced(sample.inputs.clone()).output(), &sample.targets))
+            .sum();
+
+        Ok(total / T::from_f64(samples.len().max(1) as f64))
+    }
+
+    fn check(network: &Network<T>, samples: &[Sample<T>]) -> Result<(), NetworkError> {
+        if let Some(layer) = network.layers.iter().position(|layer| layer.kind != LayerKind::Dense) {
+            return Err(NetworkError::UnsupportedLayer(layer + 1));
+        }
+
+        let topology = network.topology();
+        let (inputs, outputs) = (topology[0].neurons, topology[topology.len() - 1].neurons);
+
+        for sample in samples {
+            for (expected, actual) in [(inputs, sample.inputs.len()), (outputs, sample.targets.len())] {
+                if expected != actual {
+                    return Err(NetworkError::ShapeMismatch { expected, actual });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self, network: &mut Network<T>, gradients: &Gradients<T>) {
+        let scalar = |value: f32| T::from_f64(value as f64);
+
+        match self.optimizer {
+            Optimizer::Sgd { learning_rate } => {
+                let learning_rate = scalar(learning_rate);
+
+                for (layer, gradients) in network.layers.iter_mut().zip(&gradients.layers) {
+                    for (param, grad) in layer.params_mut().zip(gradients.params()) {
+                        *param = *param - learning_rate * grad;
+                    }
+                }
+            }
+
+            Optimizer::Adam { learning_rate, beta1, beta2, epsilon } => {
+                let (learning_rate, beta1, beta2, epsilon) = (scalar(learning_rate), scalar(beta1), scalar(beta2), scalar(epsilon));
+
+                let topology = network.topology();
+                if self.moments.as_ref().is_none_or(|moments| moments.topology != topology) {
+                    self.moments = Some(Moments {
+                        topology,
+                        m: Gradients::zeros(network),
+                        v: Gradients::zeros(network),
+                        beta1_power: T::ONE,
+                        beta2_power: T::ONE,
+                    });
+                }
+
+                let Moments { m, v, beta1_power, beta2_power, .. } = self.moments.as_mut().expect("moments were just set");
+                *beta1_power = *beta1_power * beta1;
+                *beta2_power = *beta2_power * beta2;
+
+                let m_correction = T::ONE - *beta1_power;
+                let v_correction = T::ONE - *beta2_power;
+
+                let layers = network.layers.iter_mut().zip(&gradients.layers).zip(&mut m.layers).zip(&mut v.layers);
+                for (((layer, gradients), m), v) in layers {
+                    let params = layer.params_mut().zip(gradients.params()).zip(m.params_mut()).zip(v.params_mut());
+
+                    for (((param, grad), m), v) in params {
+                        *m = beta1 * *m + (T::ONE - beta1) * grad;
+                        *v = beta2 * *v + (T::ONE - beta2) * grad * grad;
+                        *param = *param - learning_rate * (*m / m_correction) / ((*v / v_correction).sqrt() + epsilon);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Scalar> Gradients<T> {
+    fn zeros(network: &Network<T>) -> Self {
+        let layers = network
+            .layers
+            .iter()
+            .map(|layer| LayerGradients {
+                weights: vec![T::ZERO; layer.weights.len()],
+                biases: vec![T::ZERO; layer.biases.len()],
+            })
+            .collect();
+
+        Self { layers }
+    }
+
+    /// Backpropagates a single sample, adding its gradients to `self`;
+    /// returns the sample's loss.
+    fn accumulate(&mut self, network: &Network<T>, sample: &Sample<T>) -> T {
+        let trace = network.propagate_traced(sample.inputs.clone());
+        let loss = mse(trace.output(), &sample.targets);
+
+        // d(loss) / d(activation) of every neuron; a layer's entry is
+        // complete once all the layers reading it have been visited
+        let mut errors: Vec<Vec<T>> = trace.layers.iter().map(|layer| vec![T::ZERO; layer.activations.len()]).collect();
+        let output_size = T::from_f64(sample.targets.len() as f64);
+        for ((error, &output), &target) in errors[trace.layers.len() - 1].iter_mut().zip(trace.output()).zip(&sample.targets) {
+            *error = T::from_f64(2.0) * (output - target) / output_size;
+        }
+
+        for idx in (0..network.layers.len()).rev() {
+            let layer = &network.layers[idx];
+            let gradients = &mut self.layers[idx];
+            let inputs = trace.gather(network.sources(idx));
+
+            // d(loss) / d(sum) of every neuron in the current layer
+            let current = &trace.layers[idx];
+            let deltas: Vec<T> = errors[idx]
+                .iter()
+                .zip(current.sums.iter().zip(&current.activations))
+                .map(|(&error, (&sum, &activation))| error * layer.activation.derivative(sum, activation))
+                .collect();
+
+            for (neuron, &delta) in deltas.iter().enumerate() {
+                gradients.biases[neuron] += delta;
+
+                let row = &mut gradients.weights[neuron * layer.input_size..][..layer.input_size];
+                for (grad, &input) in row.iter_mut().zip(&inputs) {
+                    *grad += delta * input;
+                }
+            }
+
+            let mut input = 0;
+            for source in network.sources(idx) {
+                let width = network.width(source);
+
+                if source > 0 {
+                    for (offset, error) in errors[source - 1].iter_mut().enumerate() {
+                        *error += deltas
+                            .iter()
+                            .enumerate()
+                            .map(|(neuron, &delta)| delta * layer.weights[neuron * layer.input_size + input + offset])
+                            .sum::<T>();
+                    }
+                }
+
+                input += width;
+            }
+        }
+
+        loss
+    }
+
+    fn scale(&mut self, factor: T) {
+        for layer in &mut self.layers {
+            for grad in layer.params_mut() {
+                *grad = *grad * factor;
+            }
+        }
+    }
+}
+
+impl<T: Scalar> LayerGradients<T> {
+    fn params(&self) -> impl Iterator<Item = T> + '_ {
+        self.weights.iter().chain(&self.biases).copied()
+    }
+
+    fn params_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.weights.iter_mut().chain(&mut self.biases)
+    }
+}
+
+impl<T: Scalar> Layer<T> {
+    /// Trainable parameters, in the same order as `LayerGradients::params()`.
+    fn params_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.weights.iter_mut().chain(&mut self.biases)
+    }
+}
+
+fn mse<T: Scalar>(outputs: &[T], targets: &[T]) -> T {
+    let sum: T = outputs.iter().zip(targets).map(|(&output, &target)| (output - target) * (output - target)).sum();
+    sum / T::from_f64(targets.len() as f64)
+}
+
+#[cfg(test)]
+fn xor_samples() -> Vec<Sample> {
+    [([0.0, 0.0], 0.0), ([0.0, 1.0], 1.0), ([1.0, 0.0], 1.0), ([1.0, 1.0], 0.0)]
+        .into_iter()
+        .map(|(inputs, target)| Sample { inputs: inputs.to_vec(), targets: vec![target] })
+        .collect()
+}
+
+#[cfg(test)]
+fn assert_gradients_match_finite_differences(network: Network, sample: Sample) {
+    let mut gradients = Gradients::zeros(&network);
+    gradients.accumulate(&network, &sample);
+
+    for idx in 0..network.layers.len() {
+        let analytic: Vec<f32> = gradients.layers[idx].params().collect();
+
+        for (param, &analytic) in analytic.iter().enumerate() {
+            let loss_at = |delta: f32| {
+                let mut network = network.clone();
+                *network.layers[idx].params_mut().nth(param).unwrap() += delta;
+                Trainer::loss(&network, std::slice::from_ref(&sample)).unwrap()
+            };
+            let numeric = (loss_at(1e-2) - loss_at(-1e-2)) / 2e-2;
+
+            approx::assert_relative_eq!(analytic, numeric, epsilon = 1e-3);
+        }
+    }
+}
+
+#[test]
+fn test_gradients_match_finite_differences() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let network = Network::builder()
+        .input(2)
+        .layer(3, Activation::Tanh)
+        .layer(2, Activation::Sigmoid)
+        .build_random(&mut rng)
+        .unwrap();
+
+    assert_gradients_match_finite_differences(network, Sample { inputs: vec![0.3, -0.7], targets: vec![0.2, 0.9] });
+}
+
+#[test]
+fn test_gradients_through_skip_connections() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let network = Network::builder()
+        .input(2)
+        .layer(3, Activation::Tanh)
+        .layer(2, Activation::Tanh)
+        .layer(2, Activation::Sigmoid)
+        .sources([0, 1, 2])
+        .build_random(&mut rng)
+        .unwrap();
+
+    assert_gradients_match_finite_differences(network, Sample { inputs: vec![0.3, -0.7], targets: vec![0.2, 0.9] });
+}
+
+#[test]
+fn test_learns_xor() {
+    use rand::SeedableRng;
+
+    let samples = xor_samples();
+
+    for optimizer in [Optimizer::Sgd { learning_rate: 0.5 }, Optimizer::adam(0.05)] {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+        let mut network = Network::builder()
+            .input(2)
+            .layer(4, Activation::Tanh)
+            .layer(1, Activation::Sigmoid)
+            .initializer(Initializer::Xavier)
+            .build_random(&mut rng)
+            .unwrap();
+        let mut trainer = Trainer::new(optimizer, 2);
+
+        let initial_loss = Trainer::loss(&network, &samples).unwrap();
+        for _ in 0..2000 {
+            trainer.train_epoch(&mut rng, &mut network, &samples).unwrap();
+        }
+        let final_loss = Trainer::loss(&network, &samples).unwrap();
+
+        assert!(final_loss < 0.01, "{optimizer:?}: {initial_loss} -> {final_loss}");
+    }
+}
+
+#[test]
+fn test_rejects_bad_samples() {
+    let network = Network::builder()
+        .input(2)
+        .layer_of_kind(1, Activation::Tanh, LayerKind::Elman)
+        .build_from_weights([0.0; 4])
+        .unwrap();
+    assert_eq!(
+        Trainer::loss(&network, &xor_samples()),
+        Err(NetworkError::UnsupportedLayer(1))
+    );
+
+    let network = Network::builder()
+        .input(3)
+        .layer(1, Activation::Tanh)
+        .build_from_weights([0.0; 4])
+        .unwrap();
+    assert_eq!(
+        Trainer::loss(&network, &xor_samples()),
+        Err(NetworkError::ShapeMismatch { expected: 3, actual: 2 })
+    );
+}
+
+#[test]
+fn test_adam_starts_over_for_another_topology() {
+    use rand::SeedableRng;
+
+    let samples = xor_samples();
+    let build = |hidden: usize| {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+        Network::builder().input(2).layer(hidden, Activation::Tanh).layer(1, Activation::Sigmoid).build_random(&mut rng).unwrap()
+    };
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let mut trainer = Trainer::new(Optimizer::adam(0.05), 2);
+    trainer.train_epoch(&mut rng, &mut build(3), &samples).unwrap();
+    let mut reused = build(4);
+    trainer.train_epoch(&mut rng, &mut reused, &samples).unwrap();
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    Trainer::new(Optimizer::adam(0.05), 2).train_epoch(&mut rng, &mut build(3), &samples).unwrap();
+    let mut fresh = build(4);
+    Trainer::new(Optimizer::adam(0.05), 2).train_epoch(&mut rng, &mut fresh, &samples).unwrap();
+
+    assert_eq!(reused.weights(), fresh.weights());
+}
+
+#[test]
+fn test_fixed_training_is_reproducible() {
+    use rand::SeedableRng;
+
+    let fixed = |values: &[f32]| values.iter().map(|&value| Fixed::from_f64(value as f64)).collect();
+    let samples: Vec<Sample<Fixed>> = xor_samples()
+        .iter()
+        .map(|sample| Sample { inputs: fixed(&sample.inputs), targets: fixed(&sample.targets) })
+        .collect();
+
+    let train = || {
+        let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+        let mut network: Network<Fixed> = Network::builder()
+            .input(2)
+            .layer(4, Activation::Tanh)
+            .layer(1, Activation::Sigmoid)
+            .build_random(&mut rng)
+            .unwrap()
+            .cast();
+        let mut trainer = Trainer::new(Optimizer::Sgd { learning_rate: 0.5 }, 2);
+
+        for _ in 0..2000 {
+            trainer.train_epoch(&mut rng, &mut network, &samples).unwrap();
+        }
+        network
+    };
+
+    let (network, again) = (train(), train());
+    assert_eq!(network.weights(), again.weights());
+
+    let loss = Trainer::loss(&network, &samples).unwrap();
+    assert!(loss < Fixed::from_f64(0.01), "{loss:?}");
+    // integer arithmetic only, so this is the same on every machine
+    assert_eq!(loss.to_bits(), 22);
+}
@@ -30,21 +30,25 @@ impl Animal {
     }
 
     pub fn position(&self) -> na::Point2<f32> {
-        return self.position;
+        self.position
     }
 
     pub fn rotation(&self) -> na::Rotation2<f32> {
-        return self.rotation;
+        self.rotation
     }
 
-    pub(crate) fn from_chromosome(chromosome: ga::Chromosome, rng: &mut dyn RngCore) -> Self {
+    pub(crate) fn from_chromosome(chromosome: ga::Chromosome, kind: BrainKind, rng: &mut dyn RngCore) -> Self {
         let eye = Eye::default();
-        let brain = Brain::from_chromosome(chromosome, &eye);
+        let brain = Brain::from_chromosome(chromosome, &eye, kind);
 
         Self::new(eye, brain, rng)
     }
 
+    pub(crate) fn from_genome(genome: nn::Genome, rng: &mut dyn RngCore) -> Self {
+        Self::new(Eye::default(), Brain::from_genome(genome), rng)
+    }
+
     pub(crate) fn as_chromosome(&self) -> ga::Chromosome {
         self.brain.as_chromosome()
     }
-}
+}
\ No newline at end of file
@@ -30,7 +30,7 @@ impl AnimalIndividual{
         }
     }
 
-    pub(crate) fn into_animal(self, rng: &mut dyn RngCore) -> Animal {
-        Animal::from_chromosome(self.chromosome, rng)
+    pub(crate) fn into_animal(self, kind: BrainKind, rng: &mut dyn RngCore) -> Animal {
+        Animal::from_chromosome(self.chromosome, kind, rng)
     }
 }
\ No newline at end of file
@@ -1,37 +1,189 @@
 use crate::*;
 
+/// Which kind of brain a simulation's animals are born with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BrainKind {
+    /// Fixed topology, evolved through its weights by lib-genetic-algorithm
+    #[default]
+    Layered,
+    /// NEAT genome, whose topology evolves along with its weights
+    Neat,
+    /// Continuous-time recurrent network, evolved like `Layered`
+    Ctrnn,
+    /// Layered network that keeps learning during its lifetime, evolved
+    /// through its starting weights and Hebbian learning rules
+    Plastic,
+}
+
 #[derive(Debug)]
-pub struct Brain {
-    pub(crate) nn: nn::Network,
+pub enum Brain {
+    Layered(nn::Network),
+    Neat {
+        genome: nn::Genome,
+        network: nn::NeatNetwork,
+    },
+    Ctrnn(nn::Ctrnn),
+    Plastic(nn::PlasticNetwork),
 }
 
 impl Brain {
+    /// Number of values the brain responds with: speed and rotation
+    pub(crate) const OUTPUTS: usize = 2;
+
+    /// Number of neurons of CTRNN brains, outputs included
+    const CTRNN_NEURONS: usize = 6;
+
+    /// Time a CTRNN brain integrates over during a single simulation step
+    const CTRNN_DT: f32 = 0.1;
+
+    /// How fast plastic brains learn
+    const LEARNING_RATE: f32 = 0.01;
+
     pub fn random(rng: &mut dyn RngCore, eye: &Eye) -> Self {
-        Self {
-            nn: nn::Network::random(rng, &Self::topology(eye)),
+        Self::Layered(nn::Network::random(rng, &Self::topology(eye, nn::Activation::Identity)))
+    }
+
+    pub fn random_ctrnn(rng: &mut dyn RngCore, eye: &Eye) -> Self {
+        Self::Ctrnn(nn::Ctrnn::random(rng, Self::ctrnn_topology(eye)))
+    }
+
+    pub fn random_plastic(rng: &mut dyn RngCore, eye: &Eye) -> Self {
+        Self::Plastic(nn::PlasticNetwork::random(rng, &Self::topology(eye, nn::Activation::Tanh), Self::LEARNING_RATE))
+    }
+
+    pub fn from_genome(genome: nn::Genome) -> Self {
+        let network = genome.network();
+        Self::Neat { genome, network }
+    }
+
+    pub fn network(&self) -> Option<&nn::Network> {
+        match self {
+            Self::Layered(network) => Some(network),
+            Self::Neat { .. } | Self::Ctrnn(_) | Self::Plastic(_) => None,
+        }
+    }
+
+    /// Has a layered brain post-process its response through `head`; other
+    /// brains respond as they are.
+    pub(crate) fn set_head(&mut self, head: &nn::OutputHead) {
+        if let Self::Layered(network) = self {
+            network.set_head(head.clone()).expect("head doesn't fit the brain's outputs");
+        }
+    }
+
+    pub fn genome(&self) -> Option<&nn::Genome> {
+        match self {
+            Self::Layered(_) | Self::Ctrnn(_) | Self::Plastic(_) => None,
+            Self::Neat { genome, .. } => Some(genome),
+        }
+    }
+
+    pub(crate) fn propagate(&mut self, vision: &[f32]) -> Vec<f32> {
+        match self {
+            Self::Layered(network) => network.propagate(vision.to_vec()),
+            Self::Neat { network, .. } => network.propagate(vision),
+            Self::Ctrnn(network) => network.propagate(vision, Self::CTRNN_DT),
+            Self::Plastic(network) => network.propagate(vision.to_vec()),
         }
     }
 
     pub(crate) fn as_chromosome(&self) -> ga::Chromosome {
-        ga::Chromosome::new(self.nn.weights())
+        match self {
+            Self::Layered(network) => ga::Chromosome::new(network.weights()),
+            Self::Neat { .. } => panic!("NEAT brains evolve through their genome"),
+            Self::Ctrnn(network) => ga::Chromosome::new(network.weights()),
+            Self::Plastic(network) => ga::Chromosome::new(network.weights()),
+        }
     }
 
-    pub(crate) fn from_chromosome(chromosome: ga::Chromosome, eye: &Eye) -> Self {
-        Self {
-            nn: nn::Network::from_weights(&Self::topology(eye), chromosome),
+    pub(crate) fn from_chromosome(chromosome: ga::Chromosome, eye: &Eye, kind: BrainKind) -> Self {
+        match kind {
+            BrainKind::Layered => Self::Layered(nn::Network::from_weights(&Self::topology(eye, nn::Activation::Identity), chromosome)),
+            BrainKind::Neat => panic!("NEAT brains evolve through their genome"),
+            BrainKind::Ctrnn => Self::Ctrnn(
+                nn::Ctrnn::from_weights(Self::ctrnn_topology(eye), chromosome).expect("got a chromosome of wrong size"),
+            ),
+            BrainKind::Plastic => Self::Plastic(
+                nn::PlasticNetwork::from_weights(&Self::topology(eye, nn::Activation::Tanh), Self::LEARNING_RATE, chromosome)
+                    .expect("got a chromosome of wrong size"),
+            ),
+        }
+    }
+
+    /// Lengths of the consecutive blocks of genes making up a single neuron
+    /// (or, for plastic brains, a single learning rule), which crossover
+    /// keeps whole.
+    pub(crate) fn gene_blocks(eye: &Eye, kind: BrainKind) -> Vec<usize> {
+        match kind {
+            BrainKind::Layered => nn::Network::neuron_genes(&Self::topology(eye, nn::Activation::Identity)),
+            // (NEAT brains evolve through their genome)
+            BrainKind::Neat => Vec::new(),
+            BrainKind::Ctrnn => {
+                let topology = Self::ctrnn_topology(eye);
+                vec![nn::Ctrnn::weight_count(&topology) / topology.neurons; topology.neurons]
+            }
+            BrainKind::Plastic => {
+                let topology = Self::topology(eye, nn::Activation::Tanh);
+                let rule_genes = nn::PlasticNetwork::weight_count(&topology) - nn::Network::weight_count(&topology);
+
+                let mut blocks = nn::Network::neuron_genes(&topology);
+                blocks.extend(std::iter::repeat_n(4, rule_genes / 4));
+                blocks
+            }
+        }
+    }
+
+    fn ctrnn_topology(eye: &Eye) -> nn::CtrnnTopology {
+        nn::CtrnnTopology {
+            inputs: eye.cells(),
+            neurons: Self::CTRNN_NEURONS,
+            outputs: Self::OUTPUTS,
+            activation: nn::Activation::Tanh,
         }
     }
 
     //edit topology here
-    fn topology(eye: &Eye) -> [nn::LayerTopology; 3] {
+    /// Layered brains leave their `output` layer as `Identity`, since their
+    /// head squashes its values anyway.
+    fn topology(eye: &Eye, output: nn::Activation) -> [nn::LayerTopology; 3] {
         [
             nn::LayerTopology {
                 neurons: eye.cells(),
+                activation: nn::Activation::Identity,
+                kind: nn::LayerKind::Dense,
+                sources: nn::Sources::default(),
             },
             nn::LayerTopology {
                 neurons: 2 * eye.cells(),
+                activation: nn::Activation::ReLU,
+                kind: nn::LayerKind::Dense,
+                sources: nn::Sources::default(),
+            },
+            // zero-centered, so that both speed and rotation can go either way
+            nn::LayerTopology {
+                neurons: Self::OUTPUTS,
+                activation: output,
+                kind: nn::LayerKind::Dense,
+                sources: nn::Sources::default(),
             },
-            nn::LayerTopology { neurons: 2 },
         ]
     }
+}
+
+#[test]
+fn test_gene_blocks_cover_chromosomes() {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::from_seed(Default::default());
+    let eye = Eye::default();
+    let brains = [
+        (BrainKind::Layered, Brain::random(&mut rng, &eye)),
+        (BrainKind::Ctrnn, Brain::random_ctrnn(&mut rng, &eye)),
+        (BrainKind::Plastic, Brain::random_plastic(&mut rng, &eye)),
+    ];
+
+    for (kind, brain) in brains {
+        let blocks = Brain::gene_blocks(&eye, kind);
+        assert_eq!(blocks.iter().sum::<usize>(), brain.as_chromosome().len(), "{kind:?}");
+    }
 }
\ No newline at end of file
@@ -22,7 +22,7 @@ impl Eye{
     }
 
     pub fn cells(&self) -> usize {
-        return self.cells;
+        self.cells
     }
 
     pub fn process_vision(&self, position: &na::Point2<f32>, rotation: &na::Rotation2<f32>, foods: &[Food]) -> Vec<f32> {
@@ -50,12 +50,12 @@ impl Eye{
             cells[cell] += energy;
         }
 
-        return cells;
+        cells
     }
 }
 
 impl Default for Eye {
     fn default() -> Self {
-        return Self::new(FOV_RANGE, FOV_ANGLE, CELLS);
+        Self::new(FOV_RANGE, FOV_ANGLE, CELLS)
     }
 }
\ No newline at end of file
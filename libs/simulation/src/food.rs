@@ -12,6 +12,6 @@ impl Food {
     }
 
     pub fn position(&self) -> na::Point2<f32> {
-        return self.position;
+        self.position
     }
 }
\ No newline at end of file
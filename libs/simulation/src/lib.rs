@@ -8,7 +8,7 @@ mod eye;
 mod brain;
 
 use self::animal_individual::*;
-use lib_neural_network as nn;
+pub use lib_neural_network as nn;
 pub use lib_genetic_algorithm as ga;
 pub use nalgebra as na;
 pub use rand;
@@ -22,6 +22,19 @@ const SPEED_ACCEL: f32 = 0.2;
 const ROTATION_ACCEL: f32 = PI / 32.0;
 const GENERATION_LENGTH: usize = 2500;
 const EAT_RANGE:f32 = 0.02;
+const HALL_OF_FAME: usize = 10;
+
+/// How the genetic algorithm combines two parents' chromosomes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CrossoverKind {
+    /// Every gene comes from either parent
+    #[default]
+    Uniform,
+    /// Every neuron comes whole from either parent (see `Brain::gene_blocks()`);
+    /// NEAT brains, which don't evolve through the genetic algorithm, cross
+    /// over uniformly instead
+    LayerAware,
+}
 
 pub struct Config {
     speed_min: f32,
@@ -30,17 +43,33 @@ pub struct Config {
     rotation_accel: f32,
     generation_length: usize,
     nanimals: i32, 
-    nfood: i32
+    nfood: i32,
+    brain: BrainKind,
+    crossover: CrossoverKind,
+    /// Animals carried over unchanged into the next generation
+    elitism: usize,
 }
 
 impl Config {
     pub fn new(speed_min:f32, speed_max: f32, speed_accel:f32, rotation_accel:f32, generation_length:usize, nanimals: i32, nfood: i32) -> Self{
-        Self {speed_min, speed_max, speed_accel, rotation_accel, generation_length, nanimals, nfood}
+        Self {speed_min, speed_max, speed_accel, rotation_accel, generation_length, nanimals, nfood, brain: BrainKind::default(), crossover: CrossoverKind::default(), elitism: 0}
     }
     pub fn low_new(nanimals: i32, nfood: i32) -> Self {
         Self::new(SPEED_MIN, SPEED_MAX, SPEED_ACCEL, ROTATION_ACCEL, GENERATION_LENGTH, nanimals, nfood)
     }
-    pub fn default() -> Self {
+    pub fn with_brain(self, brain: BrainKind) -> Self {
+        Self { brain, ..self }
+    }
+    pub fn with_crossover(self, crossover: CrossoverKind) -> Self {
+        Self { crossover, ..self }
+    }
+    pub fn with_elitism(self, elitism: usize) -> Self {
+        Self { elitism, ..self }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
         Self::new(SPEED_MIN, SPEED_MAX, SPEED_ACCEL, ROTATION_ACCEL, GENERATION_LENGTH, 20, 40)
     }
 }
@@ -50,19 +79,47 @@ pub struct Simulation {
     ga: ga::GeneticAlgorithm<ga::RouletteWheelSelection>,
     age: usize,
     generation: usize,
-    config: Config
+    config: Config,
+    /// Every animal's brain, packed so the whole world thinks in one call;
+    /// `None` for brains other than `BrainKind::Layered`, which think one by one
+    brains: Option<nn::NetworkBatch>,
+    visions: Vec<f32>,
+    scratch: nn::Scratch,
+    neat: nn::Neat,
 }
 impl Simulation {
     pub fn random(rng: &mut dyn rand::RngCore, conf: Config) -> Self {
-        let world = World::random(rng, conf.nanimals, conf.nfood);
-        let ga = ga::GeneticAlgorithm::new(
-            ga::RouletteWheelSelection::default(),
-            ga::UniformCrossover::default(),
-            ga::GaussianMutation::new(0.01, 0.3)
-        );
+        let mut world = World::random(rng, conf.nanimals, conf.nfood);
+        let mut neat = nn::Neat::new(Eye::default().cells(), Brain::OUTPUTS, nn::NeatConfig::default());
+        let head = Self::output_head(&conf);
+
+        for animal in &mut world.animals {
+            match conf.brain {
+                BrainKind::Layered => animal.brain.set_head(&head),
+                BrainKind::Neat => animal.brain = Brain::from_genome(neat.genome(rng)),
+                BrainKind::Ctrnn => animal.brain = Brain::random_ctrnn(rng, &animal.eye),
+                BrainKind::Plastic => animal.brain = Brain::random_plastic(rng, &animal.eye),
+            }
+        }
+
+        let brains = world.brains();
+        let selection = ga::RouletteWheelSelection::default();
+        let mutation = ga::GaussianMutation::new(0.01, 0.3);
+        let ga = match conf.crossover {
+            CrossoverKind::Uniform => ga::GeneticAlgorithm::new(selection, ga::UniformCrossover::new(), mutation),
+            CrossoverKind::LayerAware if conf.brain == BrainKind::Neat => {
+                ga::GeneticAlgorithm::new(selection, ga::UniformCrossover::new(), mutation)
+            }
+            CrossoverKind::LayerAware => {
+                let blocks = Brain::gene_blocks(&Eye::default(), conf.brain);
+                ga::GeneticAlgorithm::new(selection, ga::LayerAwareCrossover::new(blocks), mutation)
+            }
+        }
+        .with_elitism(conf.elitism)
+        .with_hall_of_fame(HALL_OF_FAME);
 
         Self {
-            world, ga, age: 0, generation: 0, config:conf
+            world, ga, age: 0, generation: 0, config:conf, brains, visions: Vec::new(), scratch: nn::Scratch::default(), neat
         }
     }
 
@@ -74,6 +131,80 @@ impl Simulation {
         &self.world
     }
 
+    /// Best brains evolved so far; stays empty for NEAT brains, which
+    /// evolve outside of lib-genetic-algorithm.
+    pub fn hall_of_fame(&self) -> &ga::HallOfFame {
+        self.ga.hall_of_fame()
+    }
+
+    /// Mean L2 distance between every pair of brains, as a measure of the
+    /// population's diversity; `None` unless brains are layered.
+    pub fn diversity(&self) -> Option<f32> {
+        let networks: Vec<&nn::Network> = self.world.animals.iter().map(|animal| animal.brain.network()).collect::<Option<_>>()?;
+
+        let mut total = 0.0;
+        let mut pairs = 0;
+        for (idx, a) in networks.iter().enumerate() {
+            for b in &networks[idx + 1..] {
+                total += a.l2_distance(b).expect("layered brains share their topology");
+                pairs += 1;
+            }
+        }
+
+        Some(if pairs > 0 { total / pairs as f32 } else { 0.0 })
+    }
+
+    /// How far int8-quantized brains would stray from the current ones,
+    /// judged over `samples` views of the current world from random spots;
+    /// `None` unless brains are layered.
+    pub fn quantization_deviation(&self, rng: &mut dyn RngCore, samples: usize) -> Option<nn::Deviation> {
+        let eye = Eye::default();
+        let visions: Vec<Vec<f32>> = (0..samples)
+            .map(|_| eye.process_vision(&rng.gen(), &rng.gen(), &self.world.foods))
+            .collect();
+
+        let mut total = nn::Deviation::default();
+        for animal in &self.world.animals {
+            let network = animal.brain.network()?;
+            let quantized = nn::QuantizedNetwork::new(network).expect("layered brains are dense");
+            let deviation = quantized.deviation(network, visions.iter().map(Vec::as_slice));
+
+            total.max = total.max.max(deviation.max);
+            total.mean += deviation.mean / self.world.animals.len() as f32;
+        }
+
+        Some(total)
+    }
+
+    /// Prunes every brain, judging which neurons never fire over `samples`
+    /// views of the current world from random spots, and expands it back so
+    /// it keeps evolving in its simplified form; `None` unless brains are
+    /// layered.
+    pub fn prune_brains(&mut self, rng: &mut dyn RngCore, threshold: f32, samples: usize) -> Option<Vec<nn::PruneReport>> {
+        if self.config.brain != BrainKind::Layered {
+            return None;
+        }
+
+        let eye = Eye::default();
+        let visions: Vec<Vec<f32>> = (0..samples)
+            .map(|_| eye.process_vision(&rng.gen(), &rng.gen(), &self.world.foods))
+            .collect();
+
+        let mut reports = Vec::with_capacity(self.world.animals.len());
+        for animal in &mut self.world.animals {
+            let Brain::Layered(network) = &mut animal.brain else {
+                unreachable!("brains are layered");
+            };
+
+            let pruned = network.prune(threshold, visions.iter().map(Vec::as_slice)).expect("layered brains are dense");
+            *network = pruned.expand(&pruned.network).expect("pruned network expands into its own topology");
+            reports.push(pruned.report);
+        }
+
+        self.brains = self.world.brains();
+        Some(reports)
+    }
+
     fn move_animals(&mut self){
         for animal in &mut self.world.animals {
             animal.position -= animal.rotation * na::Vector2::new(0.0, animal.speed);
@@ -99,19 +230,46 @@ impl Simulation {
     }
 
     fn proc_brains(&mut self){ //, rng: &mut dyn RngCore
-        for animal in &mut self.world.animals {
+        let Some(brains) = &self.brains else {
+            for animal in &mut self.world.animals {
+                let vision = animal.eye.process_vision(&animal.position, &animal.rotation, &self.world.foods);
+                let response = animal.brain.propagate(&vision);
+                Self::steer(&self.config, animal, &response);
+            }
+            return;
+        };
+
+        self.visions.clear();
+        for animal in &self.world.animals {
             let vision = animal.eye.process_vision(&animal.position, &animal.rotation, &self.world.foods);
-            let response = animal.brain.nn.propagate(vision);
-            
-            //relavite values
-            let speed = response[0].clamp(-self.config.speed_accel, self.config.speed_accel);
-            let rotation = response[1].clamp(-self.config.rotation_accel, self.config.rotation_accel);
+            self.visions.extend(vision);
+        }
 
-            animal.speed = (animal.speed + speed).clamp(self.config.speed_min, self.config.speed_max);
-            animal.rotation = na::Rotation2::new(animal.rotation.angle() + rotation);
+        let responses = brains.propagate_into(&self.visions, &mut self.scratch);
+
+        for (animal, response) in self.world.animals.iter_mut().zip(responses.chunks_exact(Brain::OUTPUTS)) {
+            Self::steer(&self.config, animal, response);
         }
     }
 
+    /// Squashes layered brains' responses straight onto the limits of
+    /// `steer()`, so that no part of their range gets clipped away.
+    fn output_head(config: &Config) -> nn::OutputHead {
+        nn::OutputHead::Scaled(vec![
+            nn::OutputRange::symmetric(config.speed_accel),
+            nn::OutputRange::symmetric(config.rotation_accel),
+        ])
+    }
+
+    fn steer(config: &Config, animal: &mut Animal, response: &[f32]) {
+        //relavite values; only brains without a head can go past the limits
+        let speed = response[0].clamp(-config.speed_accel, config.speed_accel);
+        let rotation = response[1].clamp(-config.rotation_accel, config.rotation_accel);
+
+        animal.speed = (animal.speed + speed).clamp(config.speed_min, config.speed_max);
+        animal.rotation = na::Rotation2::new(animal.rotation.angle() + rotation);
+    }
+
 
     pub fn step(&mut self, rng: &mut dyn RngCore) -> Option<ga::Statistics> {
         self.proc_eating(rng);
@@ -147,10 +305,32 @@ impl Simulation {
     fn evolve(&mut self, rng: &mut dyn RngCore) -> ga::Statistics{
         self.age = 0;
 
-        let current_population: Vec<_> = self.world.animals.iter().map(AnimalIndividual::from_animal).collect();
-        let (evolved_population, stats) = self.ga.evolve(rng, &current_population);
+        let stats = match self.config.brain {
+            kind @ (BrainKind::Layered | BrainKind::Ctrnn | BrainKind::Plastic) => {
+                let current_population: Vec<_> = self.world.animals.iter().map(AnimalIndividual::from_animal).collect();
+                // (roulette falls back to uniform picks when every animal starved)
+                let (evolved_population, stats) = self.ga.evolve(rng, &current_population).expect("satiation is always a valid fitness");
+
+                self.world.animals = evolved_population.into_iter().map(|i| i.into_animal(kind, rng)).collect();
+
+                let head = Self::output_head(&self.config);
+                for animal in &mut self.world.animals {
+                    animal.brain.set_head(&head);
+                }
+                stats
+            }
+            BrainKind::Neat => {
+                let current_population: Vec<_> = self.world.animals.iter().map(|animal| {
+                    let genome = animal.brain.genome().expect("NEAT simulation got a layered brain");
+                    (genome.clone(), animal.satiation as f32)
+                }).collect();
+                let stats = ga::Statistics::from_fitness(current_population.iter().map(|(_, fitness)| *fitness));
 
-        self.world.animals = evolved_population.into_iter().map(|i| i.into_animal(rng)).collect();
+                self.world.animals = self.neat.evolve(rng, &current_population).expect("satiation is always a valid fitness").into_iter().map(|genome| Animal::from_genome(genome, rng)).collect();
+                stats
+            }
+        };
+        self.brains = self.world.brains();
 
         for food in &mut self.world.foods {
             food.position = rng.gen();
@@ -26,4 +26,11 @@ impl World {
     pub fn foods(&self) -> &[Food] {
         &self.foods
     }
+
+    /// Batch of every animal's brain, unless some of them aren't layered
+    /// (or can't be batched together).
+    pub(crate) fn brains(&self) -> Option<nn::NetworkBatch> {
+        let networks: Option<Vec<_>> = self.animals.iter().map(|animal| animal.brain.network()).collect();
+        networks.and_then(|networks| nn::NetworkBatch::new(networks).ok())
+    }
 }
\ No newline at end of file